@@ -1,8 +1,57 @@
 mod opengl_renderer;
+use game::core::config::DEFAULT_MAP_NAME;
+use game::game::GameMode;
+use game::map::GameMap;
+use game::monster_registry::MonsterRegistry;
+use game::tower_registry::TowerRegistry;
 use opengl_renderer::renderer::{OpenGLGame};
+use std::env;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 pub fn main() -> Result<(), String> {
-    let mut game = OpenGLGame::new();
+    let arguments: Vec<String> = env::args().skip(1).collect();
+
+    let mode = if arguments.iter().any(|argument| argument == "--maze") {
+        GameMode::Maze
+    } else {
+        GameMode::Classic
+    };
+
+    // "--endless" keeps generating waves, "--seed=<number>" replays a run.
+    let endless_seed = if arguments.iter().any(|argument| argument == "--endless") {
+        let seed = arguments
+            .iter()
+            .find_map(|argument| argument.strip_prefix("--seed="))
+            .map(|seed| seed.parse::<u64>().map_err(|error| error.to_string()))
+            .transpose()?;
+
+        Some(seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_millis() as u64)
+        }))
+    } else {
+        None
+    };
+
+    let map_name = arguments
+        .iter()
+        .find(|argument| !argument.starts_with("--"))
+        .cloned()
+        .unwrap_or_else(|| String::from(DEFAULT_MAP_NAME));
+
+    let mut map_path = "assets/maps/".to_owned();
+    map_path.push_str(&map_name);
+    map_path.push_str(".ron");
+
+    let map = GameMap::from_file(&map_path).map_err(|error| error.to_string())?;
+
+    let towers = TowerRegistry::from_directory("assets/towers").map_err(|error| error.to_string())?;
+
+    let monsters =
+        MonsterRegistry::from_directory("assets/creatures").map_err(|error| error.to_string())?;
+
+    let mut game = OpenGLGame::new(map, towers, monsters, mode, endless_seed)?;
 
     game.start_update_loop();
 
@@ -1,9 +1,13 @@
 use game::core::{GameRenderer, Point, Sprite, SpriteType};
-use game::game::Game;
+use game::game::{Game, GameMode, KeyboardShortcut};
+use game::map::GameMap;
+use game::monster_registry::MonsterRegistry;
+use game::tower_registry::TowerRegistry;
 use game::{
-    core::config::{MAP_TILES_AMOUNT_Y, SCREEN_HEIGHT, SCREEN_WIDTH, TARGET_FPS, TILE_PIXEL_SIZE},
+    core::config::TARGET_FPS,
     game::MouseState,
 };
+use sdl2::gfx::primitives::DrawRenderer;
 use sdl2::rect::Rect;
 use sdl2::render::TextureQuery;
 use sdl2::render::{Canvas, Texture, TextureCreator};
@@ -225,6 +229,19 @@ impl GameRenderer for OpenGLRenderer<'_> {
                         sprite.height,
                     ));
                 }
+                SpriteType::Circle => {
+                    self.canvas.filled_circle(
+                        sprite.position.x as i16,
+                        sprite.position.y as i16,
+                        sprite.width as i16,
+                        Color::RGBA(
+                            sprite.color.r,
+                            sprite.color.g,
+                            sprite.color.b,
+                            sprite.color.a,
+                        ),
+                    )?;
+                }
             }
         }
 
@@ -243,7 +260,13 @@ pub struct OpenGLGame {
 }
 
 impl OpenGLGame {
-    pub fn new() -> OpenGLGame {
+    pub fn new(
+        map: GameMap,
+        towers: TowerRegistry,
+        monsters: MonsterRegistry,
+        mode: GameMode,
+        endless_seed: Option<u64>,
+    ) -> Result<OpenGLGame, String> {
         let sdl_context = sdl2::init().or_else(|error| Err(error)).unwrap();
         let video_subsystem = sdl_context.video().unwrap();
 
@@ -256,14 +279,16 @@ impl OpenGLGame {
         let _image_context = sdl2::image::init(InitFlag::PNG | InitFlag::JPG);
         let event_pump = sdl_context.event_pump().unwrap();
 
-        let game = Game::new();
+        let mut game = Game::new(map, towers, monsters, mode).map_err(|error| error.to_string())?;
+
+        if let Some(seed) = endless_seed {
+            game.enable_endless_mode(seed);
+        }
+
+        let (screen_width, screen_height) = game.get_screen_size();
 
         let window = video_subsystem
-            .window(
-                "rust-sdl2 demo: Video",
-                SCREEN_HEIGHT as u32,
-                SCREEN_WIDTH as u32,
-            )
+            .window("rust-sdl2 demo: Video", screen_width, screen_height)
             .position_centered()
             .opengl()
             .build()
@@ -276,17 +301,15 @@ impl OpenGLGame {
             .map_err(|e| e.to_string())
             .unwrap();
 
-        OpenGLGame {
+        Ok(OpenGLGame {
             canvas,
             sdl_context,
             event_pump,
             game,
-        }
+        })
     }
 
     pub fn start_update_loop(&mut self) -> Result<(), String> {
-        self.game.start_round();
-
         let texture_creator = self.canvas.texture_creator();
 
         let path = match env::current_dir() {
@@ -315,7 +338,39 @@ impl OpenGLGame {
                     | Event::KeyDown {
                         keycode: Some(Keycode::Escape),
                         ..
+                    }
+                    | Event::KeyDown {
+                        keycode: Some(Keycode::Q),
+                        ..
                     } => break 'running Ok(()),
+                    Event::KeyDown {
+                        keycode: Some(Keycode::Return),
+                        ..
+                    } => self.game.handle_shortcut(KeyboardShortcut::StartGame),
+                    Event::KeyDown {
+                        keycode: Some(Keycode::P),
+                        ..
+                    } => self.game.handle_shortcut(KeyboardShortcut::TogglePause),
+                    Event::KeyDown {
+                        keycode: Some(Keycode::R),
+                        ..
+                    } => self.game.handle_shortcut(KeyboardShortcut::Restart),
+                    Event::KeyDown {
+                        keycode: Some(Keycode::U),
+                        ..
+                    } => self.game.handle_shortcut(KeyboardShortcut::UpgradeTower),
+                    Event::KeyDown {
+                        keycode: Some(Keycode::S),
+                        ..
+                    } => self.game.handle_shortcut(KeyboardShortcut::SellTower),
+                    Event::KeyDown {
+                        keycode: Some(Keycode::T),
+                        ..
+                    } => self.game.handle_shortcut(KeyboardShortcut::CycleTargetingMode),
+                    Event::KeyDown {
+                        keycode: Some(Keycode::N),
+                        ..
+                    } => self.game.handle_shortcut(KeyboardShortcut::CallNextWave),
                     _ => {}
                 }
             }
@@ -347,7 +402,7 @@ impl OpenGLGame {
 
                 sprites.push(Sprite::create_text(
                     &fps_msg,
-                    Point::new(10, TILE_PIXEL_SIZE * MAP_TILES_AMOUNT_Y as i32),
+                    Point::new(10, self.game.get_map().pixel_height()),
                     16,
                 ));
             }
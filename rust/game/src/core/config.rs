@@ -2,14 +2,20 @@ pub const TARGET_FPS: u32 = 60;
 pub const SIMULATION_TIMESTEP: f64 = 1000.0 / TARGET_FPS as f64;
 pub const MONSTER_COUNT: i32 = 50;
 pub const DELAY_BETWEEN_SPAWNING_MONSTERS: f64 = 1000.0;
-pub const SPAWN_POINT_X: i32 = 3;
-pub const SPAWN_POINT_Y: i32 = 4;
 pub const TILE_PIXEL_SIZE: i32 = 32;
 pub const SCREEN_WIDTH: i32 = 1000;
 pub const SCREEN_HEIGHT: i32 = 1000;
-pub const MAP_TILES_AMOUNT_X: usize = 23;
-pub const MAP_TILES_AMOUNT_Y: usize = 23;
-pub const MAP_TILES_TOTAL: usize = MAP_TILES_AMOUNT_X * MAP_TILES_AMOUNT_Y;
+pub const SIDE_PANEL_WIDTH: i32 = 107 * 2;
+pub const SIDE_PANEL_HEIGHT: i32 = 450 * 2;
+pub const BOTTOM_PANEL_HEIGHT: i32 = 150;
+pub const DEFAULT_MAP_NAME: &str = "default";
+pub const STARTING_GOLD: u32 = 100;
+pub const STARTING_LIFES: u8 = 10;
+pub const TOWER_SELL_REFUND_PERCENT: u32 = 75;
+/// Countdown in ms between a wave being cleared and the next one starting.
+pub const WAVE_BREAK_DURATION: f64 = 15000.0;
+/// Gold paid per second of waiting skipped by calling a wave early.
+pub const EARLY_CALL_GOLD_PER_SECOND: f64 = 1.0;
 
 // pub static SIMULATION_TIMESTEP: f64 = 1000.0 / 60.0;
 // pub static MONSTER_COUNT: i32 = 200;
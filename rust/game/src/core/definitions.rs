@@ -0,0 +1,34 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Reads `<directory>/<id>/<file_name>` for every folder in `directory`,
+/// sorted by id. Folders without the file are skipped. Errors come with the
+/// path that failed.
+pub fn read_definition_sources(
+    directory: &str,
+    file_name: &str,
+) -> Result<Vec<(String, String)>, (PathBuf, io::Error)> {
+    let directory = Path::new(directory);
+    let mut sources = vec![];
+
+    let entries = fs::read_dir(directory).map_err(|error| (directory.to_path_buf(), error))?;
+
+    for entry in entries {
+        let entry = entry.map_err(|error| (directory.to_path_buf(), error))?;
+        let definition_path = entry.path().join(file_name);
+
+        if !definition_path.is_file() {
+            continue;
+        }
+
+        let source = fs::read_to_string(&definition_path)
+            .map_err(|error| (definition_path.clone(), error))?;
+
+        sources.push((entry.file_name().to_string_lossy().to_string(), source));
+    }
+
+    sources.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    Ok(sources)
+}
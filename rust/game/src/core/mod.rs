@@ -1,4 +1,5 @@
 pub mod config;
+pub mod definitions;
 pub mod position;
 pub mod visual;
 
@@ -1,7 +1,8 @@
 use crate::core::config::TILE_PIXEL_SIZE;
+use serde::Deserialize;
 use std::ops::{Add, Div, Mul, Sub};
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Deserialize)]
 pub struct Point {
     pub x: i32,
     pub y: i32,
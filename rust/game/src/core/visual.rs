@@ -19,6 +19,8 @@ pub enum SpriteType {
     Image,
     Text,
     Rect,
+    /// Filled circle centered on `position`, with `width` as its radius.
+    Circle,
 }
 
 #[derive(Clone)]
@@ -101,6 +103,21 @@ impl Sprite {
             rotation: 0.0,
         }
     }
+
+    pub fn create_circle(color: Color, center: Point, radius: u32) -> Sprite {
+        Sprite {
+            position: center,
+            sprite_type: SpriteType::Circle,
+            texture_path: String::from(""),
+            visible: true,
+            text: String::from(""),
+            width: radius,
+            height: radius,
+            font_size: 0,
+            color,
+            rotation: 0.0,
+        }
+    }
 }
 
 pub trait GameRenderer {
@@ -0,0 +1,74 @@
+use serde::Deserialize;
+use strum_macros::IntoStaticStr;
+
+#[derive(IntoStaticStr, Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum DamageType {
+    /// Stopped by the full armor.
+    #[default]
+    Physical,
+    /// Goes through half of the armor.
+    Pierce,
+    /// Ignores armor.
+    Magic,
+    /// Ignores armor.
+    Fire,
+}
+
+impl DamageType {
+    fn get_armor_penetration(self) -> f64 {
+        match self {
+            DamageType::Physical => 0.0,
+            DamageType::Pierce => 0.5,
+            DamageType::Magic | DamageType::Fire => 1.0,
+        }
+    }
+}
+
+/// Fraction of the damage of each type a monster shrugs off. Negative
+/// values are weaknesses, -0.5 takes 50% more damage.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Resistances {
+    #[serde(default)]
+    pub physical: f64,
+    #[serde(default)]
+    pub pierce: f64,
+    #[serde(default)]
+    pub magic: f64,
+    #[serde(default)]
+    pub fire: f64,
+}
+
+impl Resistances {
+    pub fn get(&self, damage_type: DamageType) -> f64 {
+        match damage_type {
+            DamageType::Physical => self.physical,
+            DamageType::Pierce => self.pierce,
+            DamageType::Magic => self.magic,
+            DamageType::Fire => self.fire,
+        }
+    }
+
+    /// What is left of damage of the given type after the resistance.
+    pub fn get_multiplier(&self, damage_type: DamageType) -> f64 {
+        (1.0 - self.get(damage_type)).max(0.0)
+    }
+}
+
+/// Health a hit takes: the armor the damage type doesn't go through is
+/// subtracted first and the resistance scales what's left. A hit always
+/// deals at least 1 damage.
+pub fn calculate_damage(
+    damage: i32,
+    damage_type: DamageType,
+    armor: i32,
+    resistances: &Resistances,
+) -> i32 {
+    if damage <= 0 {
+        return 0;
+    }
+
+    let armor = armor as f64 * (1.0 - damage_type.get_armor_penetration());
+    let damage = (damage as f64 - armor).max(0.0) * resistances.get_multiplier(damage_type);
+
+    (damage.round() as i32).max(1)
+}
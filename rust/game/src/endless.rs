@@ -0,0 +1,202 @@
+use crate::entities::MonsterType;
+use crate::monster_registry::{MonsterDefinition, MonsterRegistry};
+use crate::wave::{SpawnGroup, Wave};
+
+/// Difficulty budget of the first wave, spent on monsters by their threat.
+const BASE_BUDGET: f64 = 1200.0;
+const BUDGET_PER_WAVE: f64 = 400.0;
+/// Added to the health multiplier with every wave.
+const HEALTH_PER_WAVE: f64 = 0.1;
+const SPEED_PER_WAVE: f64 = 0.01;
+const MAX_SPEED_MULTIPLIER: f64 = 1.5;
+const MAX_GROUPS: u32 = 3;
+/// Keeps late waves of very cheap monsters from flooding the map.
+const MAX_GROUP_SIZE: u32 = 200;
+/// Every this many waves ends with a boss.
+const BOSS_EVERY: usize = 10;
+const BOSS_HEALTH_MULTIPLIER: f64 = 10.0;
+
+/// Xorshift generator, the same seed always gives the same numbers so
+/// endless runs can be replayed and compared.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // Scrambles the seed so close seeds still start far apart, xorshift
+        // never leaves a zero state.
+        let mut state = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        state = (state ^ (state >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        state = (state ^ (state >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        state ^= state >> 31;
+
+        Rng {
+            state: if state == 0 { 1 } else { state },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+
+        self.state
+    }
+
+    /// Number in `min..=max`.
+    pub fn range(&mut self, min: u32, max: u32) -> u32 {
+        min + (self.next_u64() % (max - min + 1) as u64) as u32
+    }
+
+    /// Number in `0.0..1.0`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// How much a monster counts against the difficulty budget of a wave.
+fn get_threat(definition: &MonsterDefinition) -> f64 {
+    definition.health as f64
+        * (1.0 + definition.armor as f64 / 20.0)
+        * (definition.speed / 100.0)
+        * definition.lives_cost as f64
+}
+
+/// Generates waves once the ones of the map run out. Every wave gets its
+/// own generator seeded from the run's seed and its number, so a wave is the
+/// same whatever happened before it.
+pub struct EndlessWaves {
+    seed: u64,
+    spawns: Vec<String>,
+}
+
+impl EndlessWaves {
+    pub fn new(seed: u64, spawns: Vec<String>) -> EndlessWaves {
+        EndlessWaves { seed, spawns }
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Wave `number`, counted from 1 and including the waves of the map.
+    /// Health, speed and the budget the monsters are picked within all grow
+    /// with it.
+    pub fn generate(&self, number: usize, monster_registry: &MonsterRegistry) -> Wave {
+        let mut rng = Rng::new(self.seed ^ (number as u64).wrapping_mul(0x2545_f491_4f6c_dd1d));
+
+        let level = (number - 1) as f64;
+        let budget = BASE_BUDGET + BUDGET_PER_WAVE * level;
+        let health_multiplier = 1.0 + HEALTH_PER_WAVE * level;
+        let speed_multiplier = (1.0 + SPEED_PER_WAVE * level).min(MAX_SPEED_MULTIPLIER);
+
+        let mut monster_types: Vec<(MonsterType, f64)> = monster_registry
+            .get_monster_types()
+            .into_iter()
+            .map(|monster_type| (monster_type, get_threat(monster_registry.get(monster_type))))
+            .collect();
+        monster_types.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        // The cheapest monster is always in, even when over budget.
+        let affordable: Vec<(MonsterType, f64)> = monster_types
+            .iter()
+            .enumerate()
+            .filter(|(index, (_, threat))| *index == 0 || *threat <= budget / 2.0)
+            .map(|(_, monster_type)| *monster_type)
+            .collect();
+
+        let group_count = rng.range(1, MAX_GROUPS.min(1 + number as u32 / 4));
+        let mut delay = 0.0;
+        let mut groups = vec![];
+
+        for _ in 0..group_count {
+            let (monster_type, threat) =
+                affordable[rng.range(0, affordable.len() as u32 - 1) as usize];
+            let spawn = &self.spawns[rng.range(0, self.spawns.len() as u32 - 1) as usize];
+            let share = budget / group_count as f64;
+            let count = ((share / threat).round() as u32).clamp(1, MAX_GROUP_SIZE);
+            let interval = (rng.range(600, 1000) as f64 * 0.97f64.powi(number as i32)).max(250.0);
+
+            groups.push(SpawnGroup {
+                monster_type,
+                count,
+                spawn: spawn.clone(),
+                exit: None,
+                delay,
+                interval,
+                health_multiplier,
+                speed_multiplier,
+                boss: false,
+            });
+
+            // The next group comes in while this one is still spawning.
+            delay += interval * count as f64 * (0.3 + rng.next_f64() * 0.5);
+        }
+
+        if number.is_multiple_of(BOSS_EVERY) {
+            let (monster_type, _) = affordable[affordable.len() - 1];
+
+            groups.push(SpawnGroup {
+                monster_type,
+                count: 1,
+                spawn: self.spawns[0].clone(),
+                exit: None,
+                delay: delay + 5000.0,
+                interval: 1000.0,
+                health_multiplier: health_multiplier * BOSS_HEALTH_MULTIPLIER,
+                speed_multiplier,
+                boss: true,
+            });
+        }
+
+        Wave {
+            groups,
+            completion_bonus: 20 + number as u32 * 5,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_endless_waves(seed: u64) -> EndlessWaves {
+        EndlessWaves::new(seed, vec!["north".to_string(), "west".to_string()])
+    }
+
+    #[test]
+    fn same_seed_generates_same_waves() {
+        let monster_registry = MonsterRegistry::builtin().unwrap();
+
+        for number in 1..=20 {
+            assert_eq!(
+                create_endless_waves(42).generate(number, &monster_registry).groups,
+                create_endless_waves(42).generate(number, &monster_registry).groups
+            );
+        }
+    }
+
+    #[test]
+    fn different_seeds_generate_different_waves() {
+        let monster_registry = MonsterRegistry::builtin().unwrap();
+
+        let waves: Vec<Vec<SpawnGroup>> = (1..=20)
+            .map(|number| create_endless_waves(42).generate(number, &monster_registry).groups)
+            .collect();
+        let other_waves: Vec<Vec<SpawnGroup>> = (1..=20)
+            .map(|number| create_endless_waves(43).generate(number, &monster_registry).groups)
+            .collect();
+
+        assert_ne!(waves, other_waves);
+    }
+
+    #[test]
+    fn boss_comes_every_tenth_wave() {
+        let monster_registry = MonsterRegistry::builtin().unwrap();
+        let endless_waves = create_endless_waves(7);
+
+        assert!(!endless_waves.generate(9, &monster_registry).has_boss());
+        assert!(endless_waves.generate(10, &monster_registry).has_boss());
+    }
+}
@@ -2,13 +2,15 @@ use uuid::Uuid;
 use crate::core::config::{SIMULATION_TIMESTEP, TILE_PIXEL_SIZE};
 use crate::core::position::map_pos_to_pixel_pos;
 use crate::core::{Color, Direction, Point, Sprite};
+use crate::damage::{calculate_damage, DamageType, Resistances};
+use crate::map::GameMap;
+use crate::monster_registry::MonsterDefinition;
+use crate::status_effect::{StatusEffect, StatusEffects};
 use std::ops::Add;
-use strum_macros::IntoStaticStr;
 
-#[derive(IntoStaticStr, Clone)]
-pub enum MonsterType {
-    Creeper,
-}
+/// Id of a monster definition in the `MonsterRegistry`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MonsterType(pub(crate) usize);
 
 #[derive(PartialEq)]
 pub enum CreatureEventType {
@@ -22,69 +24,164 @@ pub struct Monster {
     pub position: Point,
     pub direction_facing: Direction,
     pub health: i32,
+    pub max_health: i32,
+    pub armor: i32,
+    pub resistances: Resistances,
     pub alive: bool,
     pub reached_final_destination: bool,
+    pub exit: String,
+    pub bounty: u32,
+    pub lives_cost: u8,
+    /// Drawn with a golden health bar so it stands out.
+    pub boss: bool,
+    /// Index of the wave that spawned it.
+    pub wave: usize,
 
-    path_to_follow: Vec<Point>,
     position_wanting_to_reach: Option<Point>,
     final_position: Point,
+    sprite_base_path: String,
     is_moving: bool,
     time_elapsed_since_last_move: f64,
     delay_between_moves: f64,
     time_from_last_change_of_frame: f64,
     change_frame_speed: f64,
+    animation_frames: i8,
     movement_frame: i8,
-    transitional_position: i8,
-    speed_in_pixels: i8,
+    transitional_position: f64,
+    /// Pixels per second.
+    speed: f64,
+    /// Cost left to reach the exit along its flow field, updated as the
+    /// monster moves.
+    distance_to_exit: f64,
+    status_effects: StatusEffects,
     time_to_simulate: f64,
 }
 
 impl Monster {
-    pub fn new(monster_type: MonsterType, position: Point, path_to_follow: Vec<Point>) -> Monster {
-        let name: &str = monster_type.into();
-        let name = name.to_lowercase();
-
+    pub fn new(
+        definition: &MonsterDefinition,
+        position: Point,
+        exit: String,
+        final_position: Point,
+    ) -> Monster {
         Monster {
             id: Uuid::new_v4(),
-            name,
+            name: definition.name.clone(),
             position,
             direction_facing: Direction::Bottom,
-            path_to_follow,
+            exit,
+            bounty: definition.bounty,
+            lives_cost: definition.lives_cost,
+            boss: false,
+            wave: 0,
+            armor: definition.armor,
+            resistances: definition.resistances.clone(),
             position_wanting_to_reach: None,
+            sprite_base_path: definition.get_sprite_base_path(),
             is_moving: false,
             time_elapsed_since_last_move: 0.0,
             delay_between_moves: 10.0,
             time_from_last_change_of_frame: 0.0,
-            change_frame_speed: 200.0,
+            change_frame_speed: definition.animation.frame_duration,
+            animation_frames: definition.animation.frames,
             movement_frame: 0,
-            transitional_position: 0,
-            speed_in_pixels: 2,
-            final_position: Point { x: 17, y: 22 },
+            transitional_position: 0.0,
+            speed: definition.speed,
+            distance_to_exit: f64::MAX,
+            status_effects: StatusEffects::default(),
+            final_position,
             reached_final_destination: false,
-            health: 100,
+            health: definition.health,
+            max_health: definition.health,
             alive: true,
             time_to_simulate: 0.0,
         }
     }
 
+    /// Multiplies both the current and the max health.
+    pub fn scale_health(&mut self, multiplier: f64) {
+        self.max_health = ((self.max_health as f64 * multiplier).round() as i32).max(1);
+        self.health = self.max_health;
+    }
+
+    pub fn scale_speed(&mut self, multiplier: f64) {
+        self.speed *= multiplier;
+    }
+
     pub fn change_direction(&mut self, direction: Direction) {
         if self.direction_facing != direction {
             self.direction_facing = direction;
         }
     }
 
+    /// Tile the monster stands on, or the one it is currently walking into.
+    pub fn get_path_origin(&self) -> Point {
+        self.position_wanting_to_reach.unwrap_or(self.position)
+    }
+
+    /// What is left of the way to the exit, used by towers to tell which
+    /// monster is furthest along its path. Measured on the flow field, so
+    /// monsters rerouted in maze mode or coming from other spawns compare
+    /// fairly.
+    pub fn get_distance_to_exit(&self) -> f64 {
+        self.distance_to_exit
+    }
+
+    /// Pixels per millisecond.
+    pub fn get_speed(&self) -> f64 {
+        self.speed / 1000.0 * self.status_effects.get_speed_multiplier()
+    }
+
+    pub fn apply_status_effect(&mut self, effect: StatusEffect) {
+        if !self.alive {
+            return;
+        }
+
+        self.status_effects.apply(effect);
+    }
+
+    /// Position in pixels including the progress towards the next tile.
+    pub fn get_pixel_position(&self) -> Point {
+        let mut x_pixels: i32 = 0;
+        let mut y_pixels: i32 = 0;
+        let transitional_position = self.transitional_position as i32;
+
+        match self.direction_facing {
+            Direction::Bottom => y_pixels += transitional_position,
+            Direction::Top => y_pixels -= transitional_position,
+            Direction::Left => x_pixels -= transitional_position,
+            Direction::Right => x_pixels += transitional_position,
+        }
+
+        let position = map_pos_to_pixel_pos(self.position);
+
+        Point {
+            x: position.x + x_pixels,
+            y: position.y + y_pixels,
+        }
+    }
+
+    /// Center of the monster as drawn.
+    pub fn get_pixel_center(&self) -> Point {
+        self.get_pixel_position() + Point::new(TILE_PIXEL_SIZE / 2, TILE_PIXEL_SIZE / 2)
+    }
+
+    /// Monsters whose health ran out are only removed on their next update,
+    /// until then they can't be targeted or hit.
+    pub fn is_alive(&self) -> bool {
+        self.alive && self.health > 0
+    }
+
+    pub fn occupies(&self, position: Point) -> bool {
+        self.position == position || self.position_wanting_to_reach == Some(position)
+    }
+
     fn update_position(&mut self, new_position: Point) {
         self.position = new_position;
     }
 
     fn change_position(&mut self, new_position: Point) {
         self.update_position(new_position);
-        self.path_to_follow = self
-            .path_to_follow
-            .iter()
-            .filter(|&&p| p != new_position)
-            .map(|x| *x)
-            .collect();
 
         self.position_wanting_to_reach = None;
     }
@@ -146,10 +243,8 @@ impl Monster {
     }
 
     fn get_sprite_texture_path(&self) -> String {
-        let mut path = "/assets/creatures/".to_owned();
+        let mut path = self.sprite_base_path.clone();
 
-        path.push_str(&self.name);
-        path.push_str("/");
         path.push_str(&self.direction_facing.get_lowercase());
         path.push_str("_");
         path.push_str(&self.movement_frame.to_string());
@@ -165,12 +260,12 @@ impl Monster {
 
         self.change_position(self.position_wanting_to_reach.unwrap());
 
-        self.transitional_position = 0;
+        self.transitional_position = 0.0;
 
         self.is_moving = false;
     }
 
-    pub fn update(&mut self, elapsed_time: f64) -> Option<CreatureEventType> {
+    pub fn update(&mut self, elapsed_time: f64, map: &GameMap) -> Option<CreatureEventType> {
         if self.health <= 0 {
             self.alive = false;
 
@@ -183,47 +278,76 @@ impl Monster {
             return Some(CreatureEventType::ReachedFinalDestination);
         }
 
-        self.update_movement(elapsed_time);
+        self.update_movement(elapsed_time, map);
 
         None
     }
 
-    pub fn update_movement(&mut self, elapsed_time: f64) {
+    pub fn update_movement(&mut self, elapsed_time: f64, map: &GameMap) {
         self.time_to_simulate += elapsed_time;
 
         while self.time_to_simulate >= SIMULATION_TIMESTEP {
-            self.update_movement_single_frame();
+            self.update_status_effects();
+            self.update_movement_single_frame(map);
             self.time_to_simulate -= SIMULATION_TIMESTEP;
         }
+
+        self.update_distance_to_exit(map);
     }
 
-    fn update_movement_single_frame(&mut self) {
-        let next_move = self.path_to_follow.get(0);
-
-        match next_move {
-            Some(_next_move) => match self.position_wanting_to_reach {
-                Some(_) => {}
-                None => match self.position.direction_towards(_next_move) {
-                    Direction::Bottom => self.move_down(),
-                    Direction::Right => self.move_right(),
-                    Direction::Top => self.move_up(),
-                    Direction::Left => self.move_left(),
-                },
-            },
-            None => {}
+    /// While walking into the next tile, the part of its cost not walked yet
+    /// is added to the distance from it.
+    fn update_distance_to_exit(&mut self, map: &GameMap) {
+        let flow_field = match map.get_flow_field(&self.exit) {
+            Some(flow_field) => flow_field,
+            None => return,
+        };
+
+        let tile = self.get_path_origin();
+
+        if let Some(distance) = flow_field.distance_from(map, tile) {
+            let step_left = match (self.position_wanting_to_reach, map.get_tile_type(tile)) {
+                (Some(_), Some(tile_type)) => {
+                    let walked = (self.transitional_position / TILE_PIXEL_SIZE as f64).min(1.0);
+
+                    (1.0 - walked) * tile_type.movement_cost() as f64
+                }
+                _ => 0.0,
+            };
+
+            self.distance_to_exit = distance as f64 + step_left;
+        }
+    }
+
+    fn update_movement_single_frame(&mut self, map: &GameMap) {
+        let next_move = match self.position_wanting_to_reach {
+            Some(_) => None,
+            None => map
+                .get_flow_field(&self.exit)
+                .and_then(|flow_field| flow_field.next_step(map, self.position)),
+        };
+
+        if let Some(_next_move) = next_move {
+            match self.position.direction_towards(&_next_move) {
+                Direction::Bottom => self.move_down(),
+                Direction::Right => self.move_right(),
+                Direction::Top => self.move_up(),
+                Direction::Left => self.move_left(),
+            }
         }
 
         if !self.is_moving {
             return;
         }
 
-        self.time_elapsed_since_last_move += SIMULATION_TIMESTEP;
+        self.time_elapsed_since_last_move +=
+            SIMULATION_TIMESTEP * self.status_effects.get_speed_multiplier();
 
         if self.delay_between_moves <= self.time_elapsed_since_last_move {
             self.handle_movement_frames(self.delay_between_moves);
 
-            if (self.transitional_position as i32) < TILE_PIXEL_SIZE {
-                self.move_by_pixels(self.speed_in_pixels);
+            if self.transitional_position < TILE_PIXEL_SIZE as f64 {
+                self.move_by_pixels(self.speed * self.delay_between_moves / 1000.0);
             } else {
                 self.move_to_desired_position();
             }
@@ -232,42 +356,39 @@ impl Monster {
         }
     }
 
+    /// Damage over time skips armor, it was already applied by the hit that
+    /// caused the effect.
+    fn update_status_effects(&mut self) {
+        let damage = self
+            .status_effects
+            .update(SIMULATION_TIMESTEP, &self.resistances);
+
+        self.health -= damage;
+    }
+
     fn handle_movement_frames(&mut self, elapsed_time: f64) {
         self.time_from_last_change_of_frame += elapsed_time;
 
         if self.time_from_last_change_of_frame >= self.change_frame_speed {
-            if self.movement_frame == 0 || self.movement_frame == 2 {
+            // Frame 0 is the standing one, walking cycles through the rest.
+            if self.movement_frame + 1 < self.animation_frames {
+                self.movement_frame += 1;
+            } else if self.animation_frames > 1 {
                 self.movement_frame = 1;
-            } else if self.movement_frame == 1 {
-                self.movement_frame = 2;
             }
 
             self.time_from_last_change_of_frame = 0.0;
         }
     }
 
-    fn move_by_pixels(&mut self, pixels: i8) {
+    fn move_by_pixels(&mut self, pixels: f64) {
         self.transitional_position += pixels;
     }
 
     pub fn get_sprites(&self) -> Vec<Sprite> {
         let mut sprites = vec![];
 
-        let mut x_pixels: i8 = 0;
-        let mut y_pixels: i8 = 0;
-
-        match self.direction_facing {
-            Direction::Bottom => y_pixels += self.transitional_position,
-            Direction::Top => y_pixels -= self.transitional_position,
-            Direction::Left => x_pixels -= self.transitional_position,
-            Direction::Right => x_pixels += self.transitional_position,
-        }
-
-        let position = map_pos_to_pixel_pos(self.position);
-        let position = Point {
-            x: position.x + x_pixels as i32,
-            y: position.y + y_pixels as i32,
-        };
+        let position = self.get_pixel_position();
 
         sprites.push(Sprite::create_image(
             &self.get_sprite_texture_path(),
@@ -284,15 +405,32 @@ impl Monster {
             4,
         ));
 
-        let full_hp = 100;
+        let health_color = if self.boss {
+            Color::new(212, 175, 55, 255)
+        } else {
+            Color::new(15, 96, 39, 255)
+        };
 
         sprites.push(Sprite::create_rect(
-            Color::new(15, 96, 39, 255),
+            health_color,
             position.add(Point { x: 1, y: -6 }),
-            (26 * self.health / full_hp) as u32,
+            (26 * self.health.max(0) / self.max_health) as u32,
             2,
         ));
 
+        // A dot for every kind of effect the monster suffers from.
+        for (index, effect) in self.status_effects.get_kinds().iter().enumerate() {
+            sprites.push(Sprite::create_rect(
+                effect.get_color(),
+                position.add(Point {
+                    x: 1 + index as i32 * 6,
+                    y: TILE_PIXEL_SIZE - 5,
+                }),
+                4,
+                4,
+            ));
+        }
+
         sprites.push(Sprite::create_text(
             &self.name,
             position.add(Point { x: 0, y: -16 }),
@@ -302,11 +440,11 @@ impl Monster {
         sprites
     }
 
-    pub fn take_damage(&mut self, damage: i32) {
+    pub fn take_damage(&mut self, damage: i32, damage_type: DamageType) {
         if !self.alive {
             return;
         }
 
-        self.health -= damage;
+        self.health -= calculate_damage(damage, damage_type, self.armor, &self.resistances);
     }
 }
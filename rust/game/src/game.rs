@@ -1,22 +1,33 @@
+use crate::endless::EndlessWaves;
 use crate::entities::{CreatureEventType, Monster};
-use crate::map::GameMap;
-use crate::pathfinding::find_path_to_end;
-use crate::{core::config::MAP_TILES_AMOUNT_X, entities::MonsterType};
+use crate::map::{GameMap, Waypoint};
+use crate::entities::MonsterType;
+use crate::pathfinding::{FlowField, PathfindingError};
+use crate::placement::PlacementError;
 use crate::{
-    core::config::{SPAWN_POINT_X, SPAWN_POINT_Y, TILE_PIXEL_SIZE},
-    wave::Wave,
+    core::config::{
+        BOTTOM_PANEL_HEIGHT, SCREEN_HEIGHT, SCREEN_WIDTH, SIDE_PANEL_HEIGHT, SIDE_PANEL_WIDTH,
+        EARLY_CALL_GOLD_PER_SECOND, STARTING_GOLD, STARTING_LIFES, WAVE_BREAK_DURATION,
+    },
+    wave::{default_waves, SpawnGroup, Wave, WaveError, WaveProgress},
 };
 use crate::{
-    core::{config::MAP_TILES_AMOUNT_Y, position::Rectangle, Color, Point, Sprite},
+    core::{
+        position::{pixel_pos_to_map_pos, Rectangle},
+        Color, Point, Sprite,
+    },
     game_commands::GameCommand,
-    tower::TowerType,
+    tower::{apply_support_auras, TowerError, TowerType},
+    tower_registry::TowerRegistry,
+    monster_registry::MonsterRegistry,
     tower_builder::TowerBuilder,
+    tower_panel::TowerPanel,
 };
 use crate::{projectile::Projectile, tower::Tower};
-use std::{
-    cell::{Cell, RefCell},
-    ops::Add,
-};
+use std::cell::RefCell;
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
 
 #[derive(Clone)]
 pub struct MouseState {
@@ -33,64 +44,226 @@ impl MouseState {
     }
 }
 
+/// Actions the front ends bind to keys.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyboardShortcut {
+    UpgradeTower,
+    SellTower,
+    CycleTargetingMode,
+    CallNextWave,
+    /// Leaves the menu.
+    StartGame,
+    TogglePause,
+    Restart,
+    /// Goes back to the menu. Front ends that can close themselves do that
+    /// instead.
+    Quit,
+}
+
+#[derive(Debug)]
+pub enum GameError {
+    Pathfinding(PathfindingError),
+    Waves(WaveError),
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::Pathfinding(error) => error.fmt(f),
+            GameError::Waves(error) => error.fmt(f),
+        }
+    }
+}
+
+impl Error for GameError {}
+
+impl From<PathfindingError> for GameError {
+    fn from(error: PathfindingError) -> GameError {
+        GameError::Pathfinding(error)
+    }
+}
+
+impl From<WaveError> for GameError {
+    fn from(error: WaveError) -> GameError {
+        GameError::Waves(error)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum GameMode {
+    Classic,
+    /// Monsters can walk over open ground and towers are obstacles they have
+    /// to path around.
+    Maze,
+}
+
+/// What the game is doing, `update` only runs the simulation while playing
+/// or between waves.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameState {
+    /// Waiting for the player to start.
+    Menu,
+    Playing,
+    Paused,
+    /// No wave is going, counting down to the next one.
+    WaveBreak,
+    /// Every wave was cleared with lives left, never reached in endless mode.
+    Victory,
+    Defeat,
+}
+
+/// Flow fields with a tile blocked, or why the tile can't be blocked.
+type RouteCheck = Result<Vec<FlowField>, PlacementError>;
+
 pub struct Game {
+    state: GameState,
     entities: RefCell<Vec<Monster>>,
     towers: RefCell<Vec<Tower>>,
     projectiles: RefCell<Vec<Projectile>>,
     waves: Vec<Wave>,
+    /// Number of waves started so far, the wave being played is the one
+    /// before this index.
+    current_wave: usize,
+    /// Time in ms until the next wave starts, only counts down between waves.
+    wave_countdown: Option<f64>,
+    /// Spawning progress of the waves being played, more than one when the
+    /// next wave was called before the others were cleared.
+    wave_progress: Vec<WaveProgress>,
+    /// Generates more waves once the ones of the map run out.
+    endless: Option<EndlessWaves>,
+    /// Highest wave started before running out of lives, the score of an
+    /// endless run.
+    highest_wave: usize,
+    /// Waves whose monsters were all killed or leaked.
+    waves_cleared: usize,
+    /// Time in ms spent playing, pauses and menus left out.
+    time_played: f64,
     mouse_state: MouseState,
+    /// Flow fields with the hovered tile blocked in maze mode, kept while the
+    /// same tile stays hovered so pathfinding doesn't rerun every frame.
+    /// Cleared whenever towers change.
+    hovered_flow_fields: RefCell<Option<(Point, RouteCheck)>>,
     map: GameMap,
+    mode: GameMode,
     last_update_call_time_elapsed_since_start: f64,
-    time_since_spawning_last_monster: f64,
-    monsters_to_spawn: Cell<i32>,
-    monster_path: Vec<Point>,
     lifes: u8,
-    delay_between_spawning_monsters: f64,
     tower_builder: TowerBuilder,
-    score: u8
+    tower_panel: TowerPanel,
+    tower_registry: Rc<TowerRegistry>,
+    monster_registry: MonsterRegistry,
+    score: u32,
+    gold: u32,
 }
 
 impl Game {
-    pub fn new() -> Game {
-        let map = GameMap::new();
+    pub fn new(
+        mut map: GameMap,
+        tower_registry: TowerRegistry,
+        monster_registry: MonsterRegistry,
+        mode: GameMode,
+    ) -> Result<Game, GameError> {
+        let waves = match &map.waves {
+            Some(definitions) => Wave::from_definitions(definitions, &map, &monster_registry)?,
+            None => default_waves(&map.spawns[0].name, &monster_registry),
+        };
 
-        let monster_path = find_path_to_end(
-            map.tiles,
-            Point {
-                x: SPAWN_POINT_X,
-                y: SPAWN_POINT_Y,
-            },
-        )
-        .unwrap();
+        let flow_fields = map.compute_flow_fields(&map.get_walkable_tiles(mode == GameMode::Maze))?;
 
-        Game {
-            monsters_to_spawn: Cell::new(1),
+        if let Some((wave, spawn, exit)) = find_unreachable_exit(&map, &flow_fields, &waves) {
+            return Err(GameError::Waves(WaveError::UnreachableExit {
+                wave,
+                spawn: spawn.to_string(),
+                exit: exit.to_string(),
+            }));
+        }
+
+        map.set_flow_fields(flow_fields);
+
+        let tower_registry = Rc::new(tower_registry);
+        let (tower_builder, tower_panel) = create_tower_interface(&map, &tower_registry);
+
+        Ok(Game {
+            state: GameState::Menu,
             entities: RefCell::new(vec![]),
             towers: RefCell::new(vec![]),
             projectiles: RefCell::new(vec![]),
-            waves: vec![Wave {
-                delay_between_spawning_monsters: 1000.0,
-                monster_type: MonsterType::Creeper,
-                monsters_count: 10,
-            }],
+            waves,
+            current_wave: 0,
+            wave_countdown: None,
+            wave_progress: vec![],
+            endless: None,
+            highest_wave: 0,
+            waves_cleared: 0,
+            time_played: 0.0,
             last_update_call_time_elapsed_since_start: 0.0,
-            time_since_spawning_last_monster: 0.0,
+            hovered_flow_fields: RefCell::new(None),
             map,
-            monster_path,
-            lifes: 10,
+            mode,
+            lifes: STARTING_LIFES,
             score: 0,
-            delay_between_spawning_monsters: 0.0,
+            gold: STARTING_GOLD,
             mouse_state: MouseState::new(false, Point::new(0, 0)),
-            tower_builder: TowerBuilder::new(Point::new(750, 200)),
+            tower_builder,
+            tower_panel,
+            tower_registry,
+            monster_registry,
+        })
+    }
+
+    pub fn get_map(&self) -> &GameMap {
+        &self.map
+    }
+
+    pub fn get_screen_size(&self) -> (u32, u32) {
+        let width = SCREEN_WIDTH.max(self.map.pixel_width() + SIDE_PANEL_WIDTH);
+        let height = SCREEN_HEIGHT
+            .max(self.map.pixel_height() + BOTTOM_PANEL_HEIGHT)
+            .max(SIDE_PANEL_HEIGHT);
+
+        (width as u32, height as u32)
+    }
+
+    /// The given exit, or the closest exit reachable from the spawn when no
+    /// exit is specified.
+    fn choose_exit(&self, spawn: &Waypoint, exit: Option<&str>) -> Option<&Waypoint> {
+        match exit {
+            Some(exit) => self.map.get_exit(exit),
+            None => self
+                .map
+                .exits
+                .iter()
+                .filter_map(|exit| {
+                    self.map
+                        .get_flow_field(&exit.name)
+                        .and_then(|flow_field| flow_field.distance_from(&self.map, spawn.position))
+                        .map(|distance| (exit, distance))
+                })
+                .min_by_key(|(_, distance)| *distance)
+                .map(|(exit, _)| exit),
         }
     }
 
-    fn spawn_monster(&self) {
-        let spawn_point: Point = Point::new(SPAWN_POINT_X, SPAWN_POINT_Y);
+    fn spawn_monster(&self, wave: usize, group: &SpawnGroup) {
+        let spawn = match self.map.get_spawn(&group.spawn) {
+            Some(spawn) => spawn,
+            None => return,
+        };
+
+        if let Some(exit) = self.choose_exit(spawn, group.exit.as_deref()) {
+            let mut monster = Monster::new(
+                self.monster_registry.get(group.monster_type),
+                spawn.position,
+                exit.name.clone(),
+                exit.position,
+            );
 
-        let monster = Monster::new(MonsterType::Creeper, spawn_point, self.monster_path.clone());
+            monster.scale_health(group.health_multiplier);
+            monster.scale_speed(group.speed_multiplier);
+            monster.boss = group.boss;
+            monster.wave = wave;
 
-        self.entities.borrow_mut().push(monster);
+            self.entities.borrow_mut().push(monster);
+        }
     }
 
     pub fn get_sprites(&self) -> Vec<Sprite> {
@@ -115,18 +288,18 @@ impl Game {
         sprites.push(Sprite::create_image(
             "/assets/interface/background.png",
             Point {
-                x: (MAP_TILES_AMOUNT_X * TILE_PIXEL_SIZE as usize) as i32,
+                x: self.map.pixel_width(),
                 y: 0,
             },
-            107 * 2,
-            450 * 2,
+            SIDE_PANEL_WIDTH as u32,
+            SIDE_PANEL_HEIGHT as u32,
             0.0,
         ));
 
         sprites.push(Sprite::create_image(
             "/assets/interface/icon_lifes.png",
             Point {
-                x: (MAP_TILES_AMOUNT_X * TILE_PIXEL_SIZE as usize) as i32 + 32,
+                x: self.map.pixel_width() + 32,
                 y: 32,
             },
             25,
@@ -137,7 +310,7 @@ impl Game {
         sprites.push(Sprite::create_text(
             &self.lifes.to_string(),
             Point {
-                x: (MAP_TILES_AMOUNT_X * TILE_PIXEL_SIZE as usize) as i32 + 32 + 25 + 10,
+                x: self.map.pixel_width() + 32 + 25 + 10,
                 y: 30,
             },
             32,
@@ -146,7 +319,7 @@ impl Game {
         sprites.push(Sprite::create_image(
             "/assets/interface/icon_score.png",
             Point {
-                x: (MAP_TILES_AMOUNT_X * TILE_PIXEL_SIZE as usize) as i32 + 32,
+                x: self.map.pixel_width() + 32,
                 y: 80,
             },
             17,
@@ -155,46 +328,196 @@ impl Game {
         ));
 
         sprites.push(Sprite::create_text(
-            &self.score.to_string(),
+            &self.get_score().to_string(),
             Point {
-                x: (MAP_TILES_AMOUNT_X * TILE_PIXEL_SIZE as usize) as i32 + 67,
+                x: self.map.pixel_width() + 67,
                 y: 80,
             },
             16,
         ));
 
+        sprites.push(Sprite::create_image(
+            "/assets/interface/icon_gold.png",
+            Point {
+                x: self.map.pixel_width() + 33,
+                y: 110,
+            },
+            15,
+            15,
+            0.0,
+        ));
+
+        sprites.push(Sprite::create_text(
+            &self.gold.to_string(),
+            Point {
+                x: self.map.pixel_width() + 67,
+                y: 110,
+            },
+            16,
+        ));
+
+        let mut wave = "Wave ".to_string();
+        wave.push_str(&self.get_current_wave_number().to_string());
+
+        if !self.is_endless() {
+            wave.push('/');
+            wave.push_str(&self.get_wave_count().to_string());
+        }
+
+        sprites.push(Sprite::create_text(
+            &wave,
+            Point {
+                x: self.map.pixel_width() + 33,
+                y: 140,
+            },
+            16,
+        ));
+
+        if let Some(endless) = &self.endless {
+            let mut msg = "Endless, seed ".to_string();
+            msg.push_str(&endless.get_seed().to_string());
+
+            sprites.push(Sprite::create_text(
+                &msg,
+                Point {
+                    x: self.map.pixel_width() + 33,
+                    y: 165,
+                },
+                12,
+            ));
+        }
+
         sprites.append(&mut self.get_next_wave_display());
         sprites.append(&mut self.tower_builder.get_sprites());
 
+        if let Some(position) = self.tower_panel.get_selected_tower() {
+            let towers = self.towers.borrow();
+
+            if let Some(tower) = towers.iter().find(|tower| tower.position == position) {
+                sprites.append(&mut self.tower_panel.get_sprites(tower));
+            }
+        }
+
+        sprites.append(&mut self.get_state_screen());
+
+        sprites
+    }
+
+    /// Menu, pause and end screens, drawn over the map.
+    fn get_state_screen(&self) -> Vec<Sprite> {
+        let (title, mut lines) = match self.state {
+            GameState::Playing | GameState::WaveBreak => return vec![],
+            GameState::Menu => {
+                let mut mode = match self.mode {
+                    GameMode::Classic => "Classic".to_string(),
+                    GameMode::Maze => "Maze".to_string(),
+                };
+
+                if let Some(endless) = &self.endless {
+                    mode.push_str(", endless, seed ");
+                    mode.push_str(&endless.get_seed().to_string());
+                }
+
+                ("Tower Defense", vec![mode, "[Enter] Start".to_string()])
+            }
+            GameState::Paused => ("Paused", vec!["[P] Resume, [R] Restart, [Q] Quit".to_string()]),
+            GameState::Victory => ("Victory", vec![]),
+            GameState::Defeat => ("Defeat", vec![]),
+        };
+
+        if self.state == GameState::Victory || self.state == GameState::Defeat {
+            let mut score = if self.is_endless() {
+                "Highest wave: ".to_string()
+            } else {
+                "Score: ".to_string()
+            };
+            score.push_str(&self.get_score().to_string());
+
+            let mut waves = "Waves survived: ".to_string();
+            waves.push_str(&self.waves_cleared.to_string());
+
+            let mut time = "Time played: ".to_string();
+            time.push_str(&format_time(self.time_played));
+
+            lines.push(score);
+            lines.push(waves);
+            lines.push(time);
+            lines.push("[R] Restart, [Q] Quit".to_string());
+        }
+
+        let mut sprites = vec![Sprite::create_rect(
+            Color::new(0, 0, 0, 180),
+            Point::new(0, 0),
+            self.map.pixel_width() as u32,
+            self.map.pixel_height() as u32,
+        )];
+
+        // Small maps would push the text off the left and top edges.
+        let x = (self.map.pixel_width() / 2 - 140).max(0);
+        let y = (self.map.pixel_height() / 2 - 80).max(0);
+
+        sprites.push(Sprite::create_text(title, Point::new(x, y), 32));
+
+        for (index, line) in lines.iter().enumerate() {
+            sprites.push(Sprite::create_text(
+                line,
+                Point::new(x, y + 56 + index as i32 * 24),
+                16,
+            ));
+        }
+
         sprites
     }
 
     fn get_next_wave_display(&self) -> Vec<Sprite> {
         let mut sprites = vec![];
-        match self.get_current_wave() {
+        match self.get_next_wave() {
             Some(wave) => {
-                let mut msg = "Next wave: ".to_string();
-                let monster_type = &wave.monster_type.to_owned();
-                let monster_name: &str = monster_type.into();
-                msg.push_str(&monster_name);
+                let mut msg = "Next wave (".to_string();
+                msg.push_str(&(self.get_current_wave_number() + 1).to_string());
+
+                if !self.is_endless() {
+                    msg.push('/');
+                    msg.push_str(&self.get_wave_count().to_string());
+                }
+
+                msg.push_str("): ");
+
+                let mut monster_types: Vec<MonsterType> = vec![];
+
+                for group in wave.groups.iter() {
+                    if !monster_types.contains(&group.monster_type) {
+                        monster_types.push(group.monster_type);
+                    }
+                }
+
+                let names: Vec<&str> = monster_types
+                    .iter()
+                    .map(|monster_type| self.monster_registry.get(*monster_type).name.as_str())
+                    .collect();
+                msg.push_str(&names.join(", "));
+
+                if wave.has_boss() {
+                    msg.push_str(" + boss");
+                }
 
                 sprites.push(Sprite::create_text(
                     &msg,
                     Point {
                         x: 30,
-                        y: (MAP_TILES_AMOUNT_Y * TILE_PIXEL_SIZE as usize) as i32 + 32 + 25 + 10,
+                        y: self.map.pixel_height() + 32 + 25 + 10,
                     },
                     16,
                 ));
 
                 let mut msg = "Number of monsters: ".to_string();
-                msg.push_str(&wave.monsters_count.to_string());
+                msg.push_str(&wave.get_monster_count().to_string());
 
                 sprites.push(Sprite::create_text(
                     &msg,
                     Point {
                         x: 30,
-                        y: (MAP_TILES_AMOUNT_Y * TILE_PIXEL_SIZE as usize) as i32
+                        y: self.map.pixel_height()
                             + 32
                             + 25
                             + 10
@@ -203,39 +526,245 @@ impl Game {
                     16,
                 ));
 
-                let mut path = "/assets/creatures/".to_string();
-                path.push_str(&monster_name.to_lowercase());
-                path.push_str("/bottom_0.png");
+                for (index, monster_type) in monster_types.iter().enumerate() {
+                    let mut path = self.monster_registry.get(*monster_type).get_sprite_base_path();
+                    path.push_str("bottom_0.png");
+
+                    sprites.push(Sprite::create_image(
+                        &path,
+                        Point {
+                            x: 30 + index as i32 * 36,
+                            y: self.map.pixel_height() + 110,
+                        },
+                        32,
+                        32,
+                        0.0,
+                    ));
+                }
+
+                if !self.wave_progress.is_empty() || self.get_wave_countdown().is_some() {
+                    let mut msg = String::new();
+
+                    if let Some(countdown) = self.get_wave_countdown() {
+                        msg.push_str("Starts in ");
+                        msg.push_str(&(countdown / 1000.0).ceil().to_string());
+                        msg.push_str("s, ");
+                    }
+
+                    msg.push_str("[N] to call it now for +");
+                    msg.push_str(&self.get_early_call_bonus().to_string());
+                    msg.push_str(" gold");
 
-                sprites.push(Sprite::create_image(
-                    &path,
+                    sprites.push(Sprite::create_text(
+                        &msg,
+                        Point {
+                            x: 34 + monster_types.len() as i32 * 36,
+                            y: self.map.pixel_height() + 118,
+                        },
+                        16,
+                    ));
+                }
+            }
+            _ => {
+                sprites.push(Sprite::create_text(
+                    "No waves left",
                     Point {
                         x: 30,
-                        y: (MAP_TILES_AMOUNT_Y * TILE_PIXEL_SIZE as usize) as i32 + 110,
+                        y: self.map.pixel_height() + 32 + 25 + 10,
                     },
-                    32,
-                    32,
-                    0.0,
+                    16,
                 ));
             }
-            _ => {}
         }
 
         sprites
     }
 
+    /// Starts the next wave, does nothing while one is still in progress or
+    /// when all of them were played.
     pub fn start_round(&mut self) {
-        match self.get_current_wave() {
-            Some(wave) => {
-                self.monsters_to_spawn.set(wave.monsters_count);
-                self.delay_between_spawning_monsters = wave.delay_between_spawning_monsters;
+        if !self.wave_progress.is_empty() {
+            return;
+        }
+
+        self.start_next_wave();
+    }
+
+    /// Starts the next wave now, alongside the ones still in progress, and
+    /// pays gold for the time it would have taken to start on its own.
+    pub fn call_next_wave(&mut self) {
+        if self.get_next_wave().is_none() {
+            return;
+        }
+
+        self.gold += self.get_early_call_bonus();
+        self.start_next_wave();
+    }
+
+    /// Gold `call_next_wave` pays now. Between waves it's the time left on
+    /// the countdown, while waves are going the time until they spawned
+    /// everything plus the whole countdown.
+    pub fn get_early_call_bonus(&self) -> u32 {
+        let remaining_time = match self.wave_countdown {
+            Some(countdown) => countdown,
+            None if self.wave_progress.is_empty() => 0.0,
+            None => {
+                self.wave_progress
+                    .iter()
+                    .map(|progress| progress.get_remaining_time(&self.waves[progress.wave]))
+                    .fold(0.0, f64::max)
+                    + WAVE_BREAK_DURATION
+            }
+        };
+
+        (remaining_time / 1000.0 * EARLY_CALL_GOLD_PER_SECOND).round() as u32
+    }
+
+    fn start_next_wave(&mut self) {
+        if let Some(wave) = self.get_next_wave() {
+            self.wave_progress.push(WaveProgress::new(self.current_wave, wave));
+            self.current_wave += 1;
+            self.wave_countdown = None;
+
+            if self.lifes > 0 {
+                self.highest_wave = self.current_wave;
+            }
+        }
+
+        self.generate_endless_wave();
+    }
+
+    /// Keeps generating waves after the last one of the map, from `seed`.
+    /// The same seed on the same map always gives the same waves.
+    pub fn enable_endless_mode(&mut self, seed: u64) {
+        let spawns = self.map.spawns.iter().map(|spawn| spawn.name.clone()).collect();
+
+        self.endless = Some(EndlessWaves::new(seed, spawns));
+        self.generate_endless_wave();
+    }
+
+    /// Makes sure there is a next wave in endless mode.
+    fn generate_endless_wave(&mut self) {
+        if let Some(endless) = &self.endless {
+            if self.current_wave >= self.waves.len() {
+                let wave = endless.generate(self.waves.len() + 1, &self.monster_registry);
+
+                self.waves.push(wave);
             }
+        }
+    }
+
+    pub fn is_endless(&self) -> bool {
+        self.endless.is_some()
+    }
+
+    /// Highest wave reached in endless mode, monsters killed otherwise.
+    pub fn get_score(&self) -> u32 {
+        if self.endless.is_some() {
+            self.highest_wave as u32
+        } else {
+            self.score
+        }
+    }
+
+    pub fn get_state(&self) -> GameState {
+        self.state
+    }
+
+    /// Leaves the menu and starts the first wave.
+    pub fn start(&mut self) {
+        if self.state != GameState::Menu {
+            return;
+        }
+
+        self.state = GameState::Playing;
+        self.start_round();
+    }
+
+    pub fn toggle_pause(&mut self) {
+        match self.state {
+            GameState::Playing | GameState::WaveBreak => self.state = GameState::Paused,
+            GameState::Paused => self.update_state(),
             _ => {}
         }
     }
 
-    fn get_current_wave(&self) -> Option<&Wave> {
-        self.waves.get(0)
+    /// Starts a new run on the same map, with the same waves in endless
+    /// mode.
+    pub fn restart(&mut self) {
+        self.return_to_menu();
+        self.start();
+    }
+
+    /// Throws the run away and goes back to the menu.
+    pub fn return_to_menu(&mut self) {
+        self.entities.borrow_mut().clear();
+        self.towers.borrow_mut().clear();
+        self.projectiles.borrow_mut().clear();
+
+        self.current_wave = 0;
+        self.wave_countdown = None;
+        self.wave_progress.clear();
+        self.highest_wave = 0;
+        self.waves_cleared = 0;
+        self.time_played = 0.0;
+        self.lifes = STARTING_LIFES;
+        self.score = 0;
+        self.gold = STARTING_GOLD;
+
+        self.hovered_flow_fields.replace(None);
+
+        let (tower_builder, tower_panel) = create_tower_interface(&self.map, &self.tower_registry);
+        self.tower_builder = tower_builder;
+        self.tower_panel = tower_panel;
+
+        // Towers were obstacles in maze mode, the paths go back to the ones
+        // of the empty map, which were already found once.
+        if self.mode == GameMode::Maze {
+            let walkable_tiles = self.map.get_walkable_tiles(true);
+
+            if let Ok(flow_fields) = self.map.compute_flow_fields(&walkable_tiles) {
+                self.map.set_flow_fields(flow_fields);
+            }
+        }
+
+        self.state = GameState::Menu;
+    }
+
+    /// Whether the simulation runs, it stands still in menus and once the
+    /// game is over.
+    fn is_running(&self) -> bool {
+        self.state == GameState::Playing || self.state == GameState::WaveBreak
+    }
+
+    fn update_state(&mut self) {
+        self.state = if self.lifes == 0 {
+            GameState::Defeat
+        } else if self.wave_countdown.is_some() {
+            GameState::WaveBreak
+        } else if self.wave_progress.is_empty() && self.get_next_wave().is_none() {
+            GameState::Victory
+        } else {
+            GameState::Playing
+        };
+    }
+
+    fn get_next_wave(&self) -> Option<&Wave> {
+        self.waves.get(self.current_wave)
+    }
+
+    /// Waves started so far, including the one in progress.
+    pub fn get_current_wave_number(&self) -> usize {
+        self.current_wave
+    }
+
+    pub fn get_wave_count(&self) -> usize {
+        self.waves.len()
+    }
+
+    /// Time in ms left until the next wave starts on its own.
+    pub fn get_wave_countdown(&self) -> Option<f64> {
+        self.wave_countdown
     }
 
     pub fn update(&mut self, time_elapsed_since_start: f64, mouse_state: MouseState) {
@@ -243,21 +772,27 @@ impl Game {
 
         let time_elapsed =
             time_elapsed_since_start - self.last_update_call_time_elapsed_since_start;
+        self.last_update_call_time_elapsed_since_start = time_elapsed_since_start;
+
+        if !self.is_running() {
+            return;
+        }
 
-        self.time_since_spawning_last_monster += time_elapsed;
+        self.time_played += time_elapsed;
 
         {
             let mut monsters = self.entities.borrow_mut();
 
             for entity in monsters.iter_mut() {
-                match entity.update(time_elapsed) {
+                match entity.update(time_elapsed, &self.map) {
                     Some(CreatureEventType::Killed) => {
                         self.score += 1;
+                        self.gold += entity.bounty;
                     }
                     Some(CreatureEventType::ReachedFinalDestination) => {
                         self.lifes = self
                             .lifes
-                            .saturating_sub(1)
+                            .saturating_sub(entity.lives_cost)
                     }
                     _ => {}
                 }
@@ -265,10 +800,11 @@ impl Game {
 
             monsters.retain(|x| !x.reached_final_destination && x.alive);
 
-
             let mut towers = self.towers.borrow_mut();
             let mut projectiles = self.projectiles.borrow_mut();
 
+            apply_support_auras(&mut towers);
+
             for tower in towers.iter_mut() {
                 tower.update(time_elapsed, &mut monsters, &mut projectiles);
             }
@@ -280,29 +816,772 @@ impl Game {
             projectiles.retain(|x| x.active);
         }
 
-        if let Some(GameCommand::BuildTower { position, .. }) =
-            self.tower_builder.update(self.mouse_state.clone())
+        self.tower_builder.set_gold(self.gold);
+
+        if let Some(tower_type) = self.tower_builder.get_chosen_tower().cloned() {
+            let hovered_position = pixel_pos_to_map_pos(self.mouse_state.position);
+            let placement = self.validate_tower_placement(hovered_position, &tower_type);
+
+            self.tower_builder.set_placement(hovered_position, placement);
+        }
+
+        if let Some(command) = self.tower_builder.update(self.mouse_state.clone()) {
+            self.execute_command(command);
+        }
+
+        self.tower_panel.set_gold(self.gold);
+
+        let command = self
+            .tower_panel
+            .update(self.mouse_state.clone(), &self.towers.borrow());
+
+        if let Some(command) = command {
+            self.execute_command(command);
+        }
+
+        self.update_spawning(time_elapsed);
+
+        if self.complete_waves() && self.wave_progress.is_empty() && self.get_next_wave().is_some()
         {
-            self.build_tower(position);
+            self.wave_countdown = Some(WAVE_BREAK_DURATION);
+        } else if let Some(countdown) = self.wave_countdown {
+            if countdown <= time_elapsed {
+                self.start_round();
+            } else {
+                self.wave_countdown = Some(countdown - time_elapsed);
+            }
+        }
+
+        self.update_state();
+    }
+
+    fn update_spawning(&mut self, time_elapsed: f64) {
+        // Borrows the fields directly, the progress is updated while the
+        // waves are borrowed.
+        let waves = &self.waves;
+        let mut spawning = vec![];
+
+        for progress in self.wave_progress.iter_mut() {
+            for group in progress.update(&waves[progress.wave], time_elapsed) {
+                spawning.push((progress.wave, group));
+            }
+        }
+
+        for (wave, group) in spawning {
+            self.spawn_monster(wave, &self.waves[wave].groups[group]);
+        }
+    }
+
+    /// Pays the completion bonus of the waves that spawned everything and
+    /// whose monsters are all gone. Returns whether any wave was completed.
+    fn complete_waves(&mut self) -> bool {
+        let entities = self.entities.borrow();
+        let waves = &self.waves;
+        let mut bonus = 0;
+        let count = self.wave_progress.len();
+
+        self.wave_progress.retain(|progress| {
+            let completed = progress.is_finished(&waves[progress.wave])
+                && !entities.iter().any(|monster| monster.wave == progress.wave);
+
+            if completed {
+                bonus += waves[progress.wave].completion_bonus;
+            }
+
+            !completed
+        });
+
+        drop(entities);
+        self.gold += bonus;
+
+        if self.lifes > 0 {
+            self.waves_cleared += count - self.wave_progress.len();
         }
 
-        if self.time_since_spawning_last_monster > self.delay_between_spawning_monsters
-            && self.monsters_to_spawn.get() > 0
+        self.wave_progress.len() < count
+    }
+
+    pub fn handle_shortcut(&mut self, shortcut: KeyboardShortcut) {
+        match shortcut {
+            KeyboardShortcut::StartGame => self.start(),
+            KeyboardShortcut::TogglePause => self.toggle_pause(),
+            KeyboardShortcut::Restart if self.state != GameState::Menu => self.restart(),
+            KeyboardShortcut::Quit => self.return_to_menu(),
+            _ if self.is_running() => {
+                let command = match shortcut {
+                    KeyboardShortcut::CallNextWave => Some(GameCommand::CallNextWave),
+                    _ => self.tower_panel.handle_shortcut(shortcut),
+                };
+
+                if let Some(command) = command {
+                    self.execute_command(command);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn execute_command(&mut self, command: GameCommand) {
+        // Commands come from the UI, which already shows why they can't be
+        // carried out, so failures are ignored here.
+        match command {
+            GameCommand::BuildTower {
+                tower_type,
+                position,
+            } => {
+                let _ = self.build_tower(position, tower_type);
+            }
+            GameCommand::UpgradeTower { position } => {
+                let _ = self.upgrade_tower(position);
+            }
+            GameCommand::SellTower { position } => {
+                let _ = self.sell_tower(position);
+            }
+            GameCommand::CycleTargetingMode { position } => {
+                let _ = self.cycle_targeting_mode(position);
+            }
+            GameCommand::CallNextWave => self.call_next_wave(),
+        }
+    }
+
+    fn get_walkable_tiles(&self) -> Vec<bool> {
+        let mut walkable_tiles = self.map.get_walkable_tiles(self.mode == GameMode::Maze);
+
+        if self.mode == GameMode::Maze {
+            for tower in self.towers.borrow().iter() {
+                if let Some(index) = self.map.index_of(tower.position) {
+                    walkable_tiles[index] = false;
+                }
+            }
+        }
+
+        walkable_tiles
+    }
+
+    /// Rules that don't need the flow fields recomputed.
+    fn check_tower_placement(
+        &self,
+        position: Point,
+        tower_type: &TowerType,
+    ) -> Result<(), PlacementError> {
+        let tile_type = self
+            .map
+            .get_tile_type(position)
+            .ok_or(PlacementError::OutOfBounds)?;
+
+        if tile_type.is_walkable() {
+            return Err(PlacementError::OnPath);
+        }
+
+        if !tile_type.is_buildable() {
+            return Err(PlacementError::BlockedByTerrain(tile_type));
+        }
+
+        if self.towers.borrow().iter().any(|tower| tower.position == position) {
+            return Err(PlacementError::Occupied);
+        }
+
+        if self.mode == GameMode::Maze
+            && self
+                .entities
+                .borrow()
+                .iter()
+                .any(|monster| monster.occupies(position))
         {
-            self.spawn_monster();
-            &self
-                .monsters_to_spawn
-                .set(&self.monsters_to_spawn.get() - 1);
+            return Err(PlacementError::OccupiedByMonster);
+        }
+
+        let cost = self.tower_registry.get(*tower_type).get_cost(1);
 
-            self.time_since_spawning_last_monster = 0.0;
+        if self.gold < cost {
+            return Err(PlacementError::NotEnoughGold { cost });
         }
 
-        self.last_update_call_time_elapsed_since_start = time_elapsed_since_start;
+        Ok(())
     }
 
-    pub fn build_tower(&self, position: Point) {
-        let tower = Tower::new(position, TowerType::Orc);
+    pub fn validate_tower_placement(
+        &self,
+        position: Point,
+        tower_type: &TowerType,
+    ) -> Result<(), PlacementError> {
+        self.check_tower_placement(position, tower_type)?;
+
+        if self.mode == GameMode::Maze {
+            let mut hovered_flow_fields = self.hovered_flow_fields.borrow_mut();
+
+            if !matches!(&*hovered_flow_fields, Some((hovered, _)) if *hovered == position) {
+                *hovered_flow_fields = Some((position, self.compute_flow_fields_blocking(position)));
+            }
+
+            if let Some((_, flow_fields)) = &*hovered_flow_fields {
+                self.check_monsters_reach_exits(flow_fields.as_ref().map_err(Clone::clone)?)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn build_tower(
+        &mut self,
+        position: Point,
+        tower_type: TowerType,
+    ) -> Result<(), PlacementError> {
+        self.check_tower_placement(position, &tower_type)?;
+
+        if self.mode == GameMode::Maze {
+            let flow_fields = self.compute_flow_fields_blocking(position)?;
+            self.check_monsters_reach_exits(&flow_fields)?;
+            self.map.set_flow_fields(flow_fields);
+            self.hovered_flow_fields.replace(None);
+        }
+
+        let definition = self.tower_registry.get(tower_type).clone();
+
+        self.gold -= definition.get_cost(1);
+
+        let tower = Tower::new(position, tower_type, definition);
 
         self.towers.borrow_mut().push(tower);
+
+        Ok(())
+    }
+
+    pub fn upgrade_tower(&mut self, position: Point) -> Result<(), TowerError> {
+        let mut towers = self.towers.borrow_mut();
+        let tower = towers
+            .iter_mut()
+            .find(|tower| tower.position == position)
+            .ok_or(TowerError::NotFound)?;
+
+        let cost = tower.get_upgrade_cost().ok_or(TowerError::MaxLevel)?;
+
+        if self.gold < cost {
+            return Err(TowerError::NotEnoughGold { cost });
+        }
+
+        tower.upgrade()?;
+        self.gold -= cost;
+
+        Ok(())
+    }
+
+    pub fn cycle_targeting_mode(&mut self, position: Point) -> Result<(), TowerError> {
+        self.towers
+            .borrow_mut()
+            .iter_mut()
+            .find(|tower| tower.position == position)
+            .ok_or(TowerError::NotFound)?
+            .cycle_targeting_mode();
+
+        Ok(())
+    }
+
+    /// Removes the tower and refunds part of the gold invested in it.
+    pub fn sell_tower(&mut self, position: Point) -> Result<u32, TowerError> {
+        let refund = {
+            let mut towers = self.towers.borrow_mut();
+            let index = towers
+                .iter()
+                .position(|tower| tower.position == position)
+                .ok_or(TowerError::NotFound)?;
+
+            towers.remove(index).get_sell_refund()
+        };
+
+        self.gold += refund;
+
+        if self.mode == GameMode::Maze {
+            // Freeing a tile can only open up routes.
+            if let Ok(flow_fields) = self.map.compute_flow_fields(&self.get_walkable_tiles()) {
+                self.map.set_flow_fields(flow_fields);
+            }
+
+            self.hovered_flow_fields.replace(None);
+        }
+
+        Ok(refund)
+    }
+
+    /// Flow fields with the given tile blocked. Fails if that would cut off
+    /// any spawn from an exit it could reach before, or from an exit a wave
+    /// sends its monsters to. Only depends on the towers, monsters are checked
+    /// separately as they move.
+    fn compute_flow_fields_blocking(
+        &self,
+        blocked_position: Point,
+    ) -> Result<Vec<FlowField>, PlacementError> {
+        let mut walkable_tiles = self.get_walkable_tiles();
+        walkable_tiles[self.map.index_of(blocked_position).unwrap()] = false;
+
+        let flow_fields = self
+            .map
+            .compute_flow_fields(&walkable_tiles)
+            .map_err(|_| PlacementError::BlocksRoute)?;
+
+        for (exit_index, exit) in self.map.exits.iter().enumerate() {
+            let flow_field = &flow_fields[exit_index];
+            let previous_flow_field = self.map.get_flow_field(&exit.name).unwrap();
+
+            for spawn in self.map.spawns.iter() {
+                let was_reachable = previous_flow_field
+                    .distance_from(&self.map, spawn.position)
+                    .is_some();
+
+                if was_reachable && flow_field.distance_from(&self.map, spawn.position).is_none() {
+                    return Err(PlacementError::BlocksRoute);
+                }
+            }
+        }
+
+        if find_unreachable_exit(&self.map, &flow_fields, &self.waves).is_some() {
+            return Err(PlacementError::BlocksRoute);
+        }
+
+        Ok(flow_fields)
+    }
+
+    /// Fails if a live monster would be stranded with the given flow fields.
+    fn check_monsters_reach_exits(&self, flow_fields: &[FlowField]) -> Result<(), PlacementError> {
+        for monster in self.entities.borrow().iter() {
+            let flow_field = match self.map.exits.iter().position(|exit| exit.name == monster.exit) {
+                Some(exit_index) => &flow_fields[exit_index],
+                None => continue,
+            };
+
+            if flow_field
+                .distance_from(&self.map, monster.get_path_origin())
+                .is_none()
+            {
+                return Err(PlacementError::BlocksRoute);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// First spawn group sent to an exit it can't reach with `flow_fields`, as
+/// the wave number counted from 1, the spawn and the exit. Groups without an
+/// exit go to the closest one they can reach.
+fn find_unreachable_exit<'a>(
+    map: &GameMap,
+    flow_fields: &[FlowField],
+    waves: &'a [Wave],
+) -> Option<(usize, &'a str, &'a str)> {
+    for (index, wave) in waves.iter().enumerate() {
+        for group in wave.groups.iter() {
+            let exit = match &group.exit {
+                Some(exit) => exit,
+                None => continue,
+            };
+
+            let reachable = match (
+                map.get_spawn(&group.spawn),
+                map.exits.iter().position(|waypoint| &waypoint.name == exit),
+            ) {
+                (Some(spawn), Some(exit_index)) => flow_fields[exit_index]
+                    .distance_from(map, spawn.position)
+                    .is_some(),
+                _ => true,
+            };
+
+            if !reachable {
+                return Some((index + 1, &group.spawn, exit));
+            }
+        }
+    }
+
+    None
+}
+
+fn create_tower_interface(
+    map: &GameMap,
+    tower_registry: &Rc<TowerRegistry>,
+) -> (TowerBuilder, TowerPanel) {
+    let tower_builder = TowerBuilder::new(
+        Point::new(map.pixel_width() + 14, 200),
+        tower_registry.clone(),
+    );
+    let tower_panel = TowerPanel::new(
+        Point::new(map.pixel_width() + 14, 320),
+        Rectangle::new(Point::new(0, 0), map.pixel_width(), map.pixel_height()),
+    );
+
+    (tower_builder, tower_panel)
+}
+
+/// "<minutes>:<seconds>" from a time in ms.
+fn format_time(time: f64) -> String {
+    let seconds = (time / 1000.0) as u64;
+
+    let mut formatted = (seconds / 60).to_string();
+    formatted.push(':');
+
+    if seconds % 60 < 10 {
+        formatted.push('0');
+    }
+
+    formatted.push_str(&(seconds % 60).to_string());
+
+    formatted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::{SIMULATION_TIMESTEP, TARGET_FPS};
+    use crate::map::TileType;
+    use crate::wave::read_wave_definitions;
+    use std::str::FromStr;
+
+    const SPAWN: Point = Point { x: 0, y: 1 };
+
+    /// Game on a map with the spawn on the left and the exit on the right of
+    /// the second row.
+    fn create_game(tiles: &[&str], mode: GameMode) -> Game {
+        let rows: Vec<String> = tiles.iter().map(|row| format!("\"{}\"", row)).collect();
+        let last_column = tiles[0].len() - 1;
+
+        let map = GameMap::from_str(&format!(
+            "(name: \"Test\", spawns: [(name: \"west\", position: (x: 0, y: 1))], \
+             exits: [(name: \"east\", position: (x: {}, y: 1))], tiles: [{}])",
+            last_column,
+            rows.join(", ")
+        ))
+        .unwrap();
+
+        Game::new(
+            map,
+            TowerRegistry::builtin().unwrap(),
+            MonsterRegistry::builtin().unwrap(),
+            mode,
+        )
+        .unwrap()
+    }
+
+    /// Game on a map with a second exit in the bottom right corner, which the
+    /// only wave sends its monsters to.
+    fn create_game_going_south(tiles: &[&str], mode: GameMode) -> Result<Game, GameError> {
+        let rows: Vec<String> = tiles.iter().map(|row| format!("\"{}\"", row)).collect();
+
+        let mut map = GameMap::from_str(&format!(
+            "(name: \"Test\", spawns: [(name: \"west\", position: (x: 0, y: 1))], \
+             exits: [(name: \"east\", position: (x: 4, y: 1)), \
+             (name: \"south\", position: (x: 4, y: 3))], tiles: [{}])",
+            rows.join(", ")
+        ))
+        .unwrap();
+        map.waves = Some(
+            read_wave_definitions(
+                "(waves: [(groups: [(monster: \"creeper\", count: 1, exit: \"south\")])])",
+            )
+            .unwrap(),
+        );
+
+        Game::new(
+            map,
+            TowerRegistry::builtin().unwrap(),
+            MonsterRegistry::builtin().unwrap(),
+            mode,
+        )
+    }
+
+    fn get_orc(game: &Game) -> TowerType {
+        game.tower_registry.find("orc").unwrap()
+    }
+
+    fn get_distance_to_exit(game: &Game) -> Option<u32> {
+        game.map
+            .get_flow_field("east")
+            .unwrap()
+            .distance_from(&game.map, SPAWN)
+    }
+
+    /// Single creeper coming from the west spawn.
+    fn create_wave(game: &Game) -> Wave {
+        Wave {
+            groups: vec![SpawnGroup {
+                monster_type: game.monster_registry.find("creeper").unwrap(),
+                count: 1,
+                spawn: "west".to_string(),
+                exit: None,
+                delay: 0.0,
+                interval: 1000.0,
+                health_multiplier: 1.0,
+                speed_multiplier: 1.0,
+                boss: false,
+            }],
+            completion_bonus: 10,
+        }
+    }
+
+    /// Updates the game frame by frame for `duration` ms.
+    fn run(game: &mut Game, duration: f64) {
+        let end = game.last_update_call_time_elapsed_since_start + duration;
+
+        while game.last_update_call_time_elapsed_since_start < end {
+            let time = game.last_update_call_time_elapsed_since_start + SIMULATION_TIMESTEP;
+            game.update(time, MouseState::new(false, Point::new(0, 0)));
+        }
+    }
+
+    /// Updates the game until it reaches `state`, for at most a minute.
+    fn run_until(game: &mut Game, state: GameState) {
+        for _ in 0..TARGET_FPS * 60 {
+            if game.get_state() == state {
+                return;
+            }
+
+            run(game, SIMULATION_TIMESTEP);
+        }
+
+        panic!("Game is still {:?} instead of {:?}", game.get_state(), state);
+    }
+
+    #[test]
+    fn monsters_path_around_towers_in_maze_mode() {
+        let mut game = create_game(&[".....", "#...#", "....."], GameMode::Maze);
+        let orc = get_orc(&game);
+
+        assert_eq!(get_distance_to_exit(&game), Some(7));
+
+        game.build_tower(Point::new(2, 1), orc).unwrap();
+        assert_eq!(get_distance_to_exit(&game), Some(11));
+
+        game.sell_tower(Point::new(2, 1)).unwrap();
+        assert_eq!(get_distance_to_exit(&game), Some(7));
+    }
+
+    #[test]
+    fn towers_do_not_change_the_path_in_classic_mode() {
+        let mut game = create_game(&[".....", "#####", "....."], GameMode::Classic);
+        let orc = get_orc(&game);
+
+        game.build_tower(Point::new(2, 0), orc).unwrap();
+        game.build_tower(Point::new(2, 2), orc).unwrap();
+
+        assert_eq!(get_distance_to_exit(&game), Some(4));
+    }
+
+    #[test]
+    fn rejects_towers_sealing_the_last_corridor_in_maze_mode() {
+        let mut game = create_game(&["^^^^^", "#...#", "^^^^^"], GameMode::Maze);
+        let orc = get_orc(&game);
+        let position = Point::new(2, 1);
+
+        assert_eq!(
+            game.validate_tower_placement(position, &orc),
+            Err(PlacementError::BlocksRoute)
+        );
+        assert_eq!(
+            game.build_tower(position, orc),
+            Err(PlacementError::BlocksRoute)
+        );
+        assert!(game.towers.borrow().is_empty());
+        assert_eq!(game.gold, STARTING_GOLD);
+        assert_eq!(get_distance_to_exit(&game), Some(7));
+    }
+
+    #[test]
+    fn rejects_towers_cutting_off_a_monster() {
+        let mut game = create_game(&[".....", "#...#", "....."], GameMode::Maze);
+        let orc = get_orc(&game);
+
+        game.build_tower(Point::new(1, 0), orc).unwrap();
+        game.build_tower(Point::new(3, 0), orc).unwrap();
+        game.gold = 1000;
+
+        // Only the tile below leads out of the nook the monster is in, the
+        // spawn has other ways to the exit.
+        let definition = game
+            .monster_registry
+            .get(game.monster_registry.get_monster_types()[0]);
+        let monster = Monster::new(
+            definition,
+            Point::new(2, 0),
+            "east".to_string(),
+            Point::new(4, 1),
+        );
+        game.entities.borrow_mut().push(monster);
+
+        assert_eq!(
+            game.build_tower(Point::new(2, 1), orc),
+            Err(PlacementError::BlocksRoute)
+        );
+        assert!(game.build_tower(Point::new(2, 2), orc).is_ok());
+    }
+
+    #[test]
+    fn rejects_towers_on_the_path_terrain_and_other_towers() {
+        let mut game = create_game(&["^....", "#####", "....."], GameMode::Classic);
+        let orc = get_orc(&game);
+
+        assert_eq!(
+            game.build_tower(Point::new(5, 0), orc),
+            Err(PlacementError::OutOfBounds)
+        );
+        assert_eq!(
+            game.build_tower(Point::new(2, 1), orc),
+            Err(PlacementError::OnPath)
+        );
+        assert_eq!(
+            game.build_tower(Point::new(0, 0), orc),
+            Err(PlacementError::BlockedByTerrain(TileType::Rock))
+        );
+
+        game.build_tower(Point::new(1, 0), orc).unwrap();
+        assert_eq!(
+            game.build_tower(Point::new(1, 0), orc),
+            Err(PlacementError::Occupied)
+        );
+
+        game.gold = 0;
+        assert_eq!(
+            game.validate_tower_placement(Point::new(2, 0), &orc),
+            Err(PlacementError::NotEnoughGold { cost: 50 })
+        );
+    }
+
+    #[test]
+    fn counts_down_between_waves() {
+        let mut game = create_game(&[".....", "#####", "....."], GameMode::Classic);
+        game.waves = vec![create_wave(&game), create_wave(&game)];
+
+        game.start();
+        assert_eq!(game.get_state(), GameState::Playing);
+        assert_eq!(game.get_current_wave_number(), 1);
+
+        run_until(&mut game, GameState::WaveBreak);
+        assert_eq!(game.gold, STARTING_GOLD + 10);
+        assert_eq!(game.waves_cleared, 1);
+
+        run(&mut game, WAVE_BREAK_DURATION / 2.0);
+        assert_eq!(game.get_state(), GameState::WaveBreak);
+
+        run(&mut game, WAVE_BREAK_DURATION / 2.0 + 100.0);
+        assert_eq!(game.get_state(), GameState::Playing);
+        assert_eq!(game.get_current_wave_number(), 2);
+        assert_eq!(game.get_wave_countdown(), None);
+    }
+
+    #[test]
+    fn calling_the_next_wave_ends_the_countdown() {
+        let mut game = create_game(&[".....", "#####", "....."], GameMode::Classic);
+        game.waves = vec![create_wave(&game), create_wave(&game)];
+
+        game.start();
+        run_until(&mut game, GameState::WaveBreak);
+        let gold = game.gold;
+
+        game.handle_shortcut(KeyboardShortcut::CallNextWave);
+        assert_eq!(game.get_current_wave_number(), 2);
+        assert!(game.gold > gold);
+
+        run(&mut game, 100.0);
+        assert_eq!(game.get_state(), GameState::Playing);
+    }
+
+    #[test]
+    fn rejects_waves_sending_monsters_to_unreachable_exits() {
+        let tiles = [".....", "#####", ".....", "..###"];
+
+        match create_game_going_south(&tiles, GameMode::Classic) {
+            Err(GameError::Waves(WaveError::UnreachableExit { wave, spawn, exit })) => {
+                assert_eq!((wave, spawn.as_str(), exit.as_str()), (1, "west", "south"));
+            }
+            _ => panic!("Exit \"south\" should be unreachable"),
+        }
+
+        // Open ground leads there in maze mode.
+        assert!(create_game_going_south(&tiles, GameMode::Maze).is_ok());
+    }
+
+    #[test]
+    fn keeps_the_exits_of_waves_reachable_in_maze_mode() {
+        let tiles = [".....", "#####", "^^^^.", "^^^^#"];
+        let mut game = create_game_going_south(&tiles, GameMode::Maze).unwrap();
+        let orc = get_orc(&game);
+
+        assert_eq!(
+            game.build_tower(Point::new(4, 2), orc),
+            Err(PlacementError::BlocksRoute)
+        );
+        assert!(game.build_tower(Point::new(3, 0), orc).is_ok());
+    }
+
+    #[test]
+    fn only_runs_while_playing() {
+        let mut game = create_game(&[".....", "#####", "....."], GameMode::Classic);
+        game.waves = vec![create_wave(&game)];
+
+        assert_eq!(game.get_state(), GameState::Menu);
+        run(&mut game, 1000.0);
+        assert_eq!(game.time_played, 0.0);
+
+        game.start();
+        run(&mut game, 100.0);
+        assert!(game.time_played > 0.0);
+
+        game.handle_shortcut(KeyboardShortcut::TogglePause);
+        assert_eq!(game.get_state(), GameState::Paused);
+        let time_played = game.time_played;
+        run(&mut game, 1000.0);
+        assert_eq!(game.time_played, time_played);
+
+        game.handle_shortcut(KeyboardShortcut::TogglePause);
+        assert_eq!(game.get_state(), GameState::Playing);
+    }
+
+    #[test]
+    fn ends_in_victory_once_every_wave_is_cleared() {
+        let mut game = create_game(&[".....", "#####", "....."], GameMode::Classic);
+        game.waves = vec![create_wave(&game)];
+
+        game.start();
+        run_until(&mut game, GameState::Victory);
+        assert_eq!(game.waves_cleared, 1);
+    }
+
+    #[test]
+    fn ends_in_defeat_when_the_last_life_is_lost() {
+        let mut game = create_game(&[".....", "#####", "....."], GameMode::Classic);
+        game.waves = vec![create_wave(&game), create_wave(&game)];
+        game.lifes = 1;
+
+        game.start();
+        run_until(&mut game, GameState::Defeat);
+        assert_eq!(game.lifes, 0);
+        assert_eq!(game.waves_cleared, 0);
+
+        // Nothing moves anymore, not even the countdown to the next wave.
+        let time_played = game.time_played;
+        run(&mut game, WAVE_BREAK_DURATION * 2.0);
+        assert_eq!(game.get_state(), GameState::Defeat);
+        assert_eq!(game.time_played, time_played);
+        assert_eq!(game.get_current_wave_number(), 1);
+    }
+
+    #[test]
+    fn restarts_and_quits_to_the_menu() {
+        let mut game = create_game(&[".....", "#####", "....."], GameMode::Classic);
+        let orc = get_orc(&game);
+        game.waves = vec![create_wave(&game), create_wave(&game)];
+        game.lifes = 1;
+
+        game.start();
+        game.build_tower(Point::new(0, 0), orc).unwrap();
+        run_until(&mut game, GameState::Defeat);
+
+        game.handle_shortcut(KeyboardShortcut::Restart);
+        assert_eq!(game.get_state(), GameState::Playing);
+        assert_eq!(game.lifes, STARTING_LIFES);
+        assert_eq!(game.gold, STARTING_GOLD);
+        assert_eq!(game.get_current_wave_number(), 1);
+        assert!(game.towers.borrow().is_empty());
+
+        game.handle_shortcut(KeyboardShortcut::Quit);
+        assert_eq!(game.get_state(), GameState::Menu);
+        assert_eq!(game.get_current_wave_number(), 0);
     }
 }
@@ -5,4 +5,16 @@ pub enum GameCommand {
         tower_type: TowerType,
         position: Point,
     },
+    UpgradeTower {
+        position: Point,
+    },
+    SellTower {
+        position: Point,
+    },
+    CycleTargetingMode {
+        position: Point,
+    },
+    /// Starts the next wave right away, even while others are still going,
+    /// for a bonus growing with the time it would have taken to start.
+    CallNextWave,
 }
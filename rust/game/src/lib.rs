@@ -2,12 +2,19 @@ extern crate strum;
 extern crate strum_macros;
 
 pub mod core;
+pub mod damage;
 pub mod pathfinding;
 pub mod map;
 pub mod entities;
+pub mod monster_registry;
 pub mod projectile;
+pub mod status_effect;
 pub mod tower;
+pub mod tower_registry;
 pub mod wave;
+pub mod endless;
 pub mod game_commands;
+pub mod placement;
 pub mod tower_builder;
+pub mod tower_panel;
 pub mod game;
@@ -1,11 +1,81 @@
-use crate::core::config::{MAP_TILES_AMOUNT_X, MAP_TILES_AMOUNT_Y, MAP_TILES_TOTAL, TILE_PIXEL_SIZE};
+use crate::core::config::TILE_PIXEL_SIZE;
 use crate::core::position::map_pos_to_pixel_pos;
-use crate::core::{Point, Sprite, SpriteType};
+use crate::core::{Color, Point, Sprite};
+use crate::pathfinding::{FlowField, PathfindingError};
+use crate::wave::{read_wave_definitions, WaveDefinition, WaveError};
+use ron::extensions::Extensions;
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use strum_macros::IntoStaticStr;
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(IntoStaticStr, Copy, Clone, PartialEq, Debug)]
 pub enum TileType {
     Road,
-    None,
+    Mud,
+    Ground,
+    Rock,
+    Water,
+    Decoration,
+}
+
+impl TileType {
+    pub fn from_glyph(glyph: char) -> Option<TileType> {
+        match glyph {
+            '#' => Some(TileType::Road),
+            '%' => Some(TileType::Mud),
+            '.' => Some(TileType::Ground),
+            '^' => Some(TileType::Rock),
+            '~' => Some(TileType::Water),
+            '*' => Some(TileType::Decoration),
+            _ => None,
+        }
+    }
+
+    /// Tiles monsters can always walk on.
+    pub fn is_walkable(&self) -> bool {
+        matches!(self, TileType::Road | TileType::Mud)
+    }
+
+    /// Ground towers are built on. Monsters walk over it in maze mode.
+    pub fn is_open_ground(&self) -> bool {
+        *self == TileType::Ground
+    }
+
+    pub fn is_buildable(&self) -> bool {
+        *self == TileType::Ground
+    }
+
+    /// Cost of stepping onto the tile, monsters prefer paths with lower total.
+    pub fn movement_cost(&self) -> u32 {
+        match self {
+            TileType::Road => 1,
+            TileType::Ground => 2,
+            TileType::Mud => 3,
+            TileType::Rock | TileType::Water | TileType::Decoration => 1,
+        }
+    }
+
+    fn get_texture_path(&self) -> Option<&'static str> {
+        match self {
+            TileType::Road | TileType::Mud => Some("/assets/tiles/ground_1.png"),
+            TileType::Ground | TileType::Decoration => Some("/assets/tiles/ice_1.png"),
+            TileType::Rock | TileType::Water => None,
+        }
+    }
+
+    fn get_overlay_color(&self) -> Option<Color> {
+        match self {
+            TileType::Mud => Some(Color::new(60, 30, 10, 120)),
+            TileType::Rock => Some(Color::new(90, 90, 90, 255)),
+            TileType::Water => Some(Color::new(30, 80, 160, 255)),
+            TileType::Decoration => Some(Color::new(40, 110, 50, 160)),
+            TileType::Road | TileType::Ground => None,
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -14,97 +84,457 @@ pub struct Tile {
     pub tile_type: TileType,
 }
 
-static NONE: TileType = TileType::None; // 436
-static ROAD: TileType = TileType::Road; // 93 + 436
-
-#[rustfmt::skip]
-static DEFAULT_MAP: [[TileType; MAP_TILES_AMOUNT_X]; MAP_TILES_AMOUNT_Y] = [
-    [NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE],
-    [NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE],
-    [NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE],
-    [NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE],
-    [NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, ROAD, ROAD, ROAD, ROAD, NONE, NONE, NONE, ROAD, ROAD, ROAD, ROAD, ROAD, NONE, NONE, NONE],
-    [NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE],
-    [NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE],
-    [NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE],
-    [NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE],
-    [NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE],
-    [NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE],
-    [NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, ROAD, ROAD, ROAD, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE],
-    [NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, ROAD, NONE, NONE, NONE],
-    [NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, ROAD, NONE, NONE, NONE],
-    [NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, ROAD, NONE, NONE, NONE],
-    [NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, ROAD, ROAD, ROAD, ROAD, ROAD, ROAD, ROAD, ROAD, NONE, NONE, NONE],
-    [NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE],
-    [NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE],
-    [NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE],
-    [NONE, NONE, NONE, ROAD, ROAD, ROAD, ROAD, ROAD, NONE, NONE, NONE, ROAD, ROAD, ROAD, ROAD, ROAD, ROAD, ROAD, NONE, NONE, NONE, NONE, NONE],
-    [NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, NONE, NONE],
-    [NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, NONE, NONE],
-    [NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, ROAD, NONE, NONE, NONE, NONE, NONE],
-  ];
+pub const BUILTIN_MAPS: [(&str, &str); 1] = [("default", include_str!("../../assets/maps/default.ron"))];
+
+/// Wave files of the built-in maps, maps without one get the default waves.
+pub const BUILTIN_WAVES: [(&str, &str); 1] = [("default", include_str!("../../assets/maps/default.waves.ron"))];
+
+const WAVE_FILE_EXTENSION: &str = "waves.ron";
+
+#[derive(Debug)]
+pub enum MapError {
+    Io { path: String, message: String },
+    Syntax(String),
+    UnknownMap(String),
+    Empty,
+    MalformedRow { row: usize, expected: usize, found: usize },
+    UnknownGlyph { row: usize, column: usize, glyph: char },
+    DuplicateWaypoint(String),
+    WaypointOutsideOfMap { name: String, position: Point },
+    MissingSpawn,
+    MissingExit,
+    Waves(WaveError),
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MapError::Io { path, message } => write!(f, "Can not read map file {}: {}", path, message),
+            MapError::Syntax(message) => write!(f, "Map file is not valid RON: {}", message),
+            MapError::UnknownMap(name) => write!(f, "There is no built-in map called \"{}\"", name),
+            MapError::Empty => write!(f, "Map has no tiles"),
+            MapError::MalformedRow { row, expected, found } => write!(
+                f,
+                "Row {} of the map must be {} tiles long like the first row, found {}",
+                row, expected, found
+            ),
+            MapError::UnknownGlyph { row, column, glyph } => write!(
+                f,
+                "Unknown tile glyph '{}' at row {}, column {}",
+                glyph, row, column
+            ),
+            MapError::DuplicateWaypoint(name) => write!(
+                f,
+                "Spawn or exit name \"{}\" is used more than once",
+                name
+            ),
+            MapError::WaypointOutsideOfMap { name, position } => write!(
+                f,
+                "Spawn or exit \"{}\" at ({}, {}) is outside of the map",
+                name, position.x, position.y
+            ),
+            MapError::MissingSpawn => write!(f, "Map has no spawn points, add at least one to \"spawns\""),
+            MapError::MissingExit => write!(f, "Map has no exit points, add at least one to \"exits\""),
+            MapError::Waves(error) => error.fmt(f),
+        }
+    }
+}
+
+impl Error for MapError {}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Waypoint {
+    pub name: String,
+    pub position: Point,
+}
+
+#[derive(Deserialize)]
+struct MapDefinition {
+    name: String,
+    #[serde(default)]
+    background: Option<String>,
+    spawns: Vec<Waypoint>,
+    exits: Vec<Waypoint>,
+    tiles: Vec<String>,
+}
 
 pub struct GameMap {
-    pub tiles_map: [[TileType; MAP_TILES_AMOUNT_X]; MAP_TILES_AMOUNT_Y],
-    pub tiles: [Tile; MAP_TILES_TOTAL],
+    pub name: String,
+    pub background: Option<String>,
+    pub width: usize,
+    pub height: usize,
+    pub spawns: Vec<Waypoint>,
+    pub exits: Vec<Waypoint>,
+    pub tiles: Vec<Tile>,
+    /// From the wave file next to the map, if it has one.
+    pub waves: Option<Vec<WaveDefinition>>,
+
+    flow_fields: Vec<FlowField>,
 }
 
 impl GameMap {
-    pub fn new() -> GameMap {
-        let tiles_map = DEFAULT_MAP;
-        let mut tiles: [Tile; MAP_TILES_TOTAL] = [Tile {
-            position: Point { x: 0, y: 0 },
-            tile_type: TileType::None,
-        }; MAP_TILES_TOTAL];
-
-        let mut tile_index = 0;
-
-        for (row_index, row) in tiles_map.iter().enumerate() {
-            for (column_index, tile_type) in row.iter().enumerate() {
-                tiles[tile_index] = Tile {
-                    position: Point {
-                        x: column_index as i32,
-                        y: row_index as i32, //CONFIG_MAP_TILES_AMOUNT_Y - row_index as i32 - 1,
-                    },
-                    tile_type: *tile_type,
+    pub fn from_name(name: &str) -> Result<GameMap, MapError> {
+        let mut map = match BUILTIN_MAPS.iter().find(|(map_name, _)| *map_name == name) {
+            Some((_, source)) => GameMap::from_str(source)?,
+            None => return Err(MapError::UnknownMap(name.to_string())),
+        };
+
+        if let Some((_, source)) = BUILTIN_WAVES.iter().find(|(map_name, _)| *map_name == name) {
+            map.waves = Some(read_wave_definitions(source).map_err(MapError::Waves)?);
+        }
+
+        Ok(map)
+    }
+
+    /// Also reads the waves from `<name>.waves.ron` next to `<name>.ron`.
+    pub fn from_file(path: &str) -> Result<GameMap, MapError> {
+        let source = fs::read_to_string(path).map_err(|error| MapError::Io {
+            path: path.to_string(),
+            message: error.to_string(),
+        })?;
+
+        let mut map = GameMap::from_str(&source)?;
+
+        let waves_path = Path::new(path).with_extension(WAVE_FILE_EXTENSION);
+
+        if waves_path.is_file() {
+            let source = fs::read_to_string(&waves_path).map_err(|error| MapError::Io {
+                path: waves_path.display().to_string(),
+                message: error.to_string(),
+            })?;
+
+            map.waves = Some(read_wave_definitions(&source).map_err(MapError::Waves)?);
+        }
+
+        Ok(map)
+    }
+
+    fn from_definition(definition: MapDefinition) -> Result<GameMap, MapError> {
+        let height = definition.tiles.len();
+        let width = match definition.tiles.first() {
+            Some(first_row) => first_row.chars().count(),
+            None => 0,
+        };
+
+        if width == 0 {
+            return Err(MapError::Empty);
+        }
+
+        let mut tiles = Vec::with_capacity(width * height);
+
+        for (row_index, row) in definition.tiles.iter().enumerate() {
+            let row_length = row.chars().count();
+
+            if row_length != width {
+                return Err(MapError::MalformedRow {
+                    row: row_index,
+                    expected: width,
+                    found: row_length,
+                });
+            }
+
+            for (column_index, glyph) in row.chars().enumerate() {
+                let position = Point::new(column_index as i32, row_index as i32);
+
+                let tile_type = match TileType::from_glyph(glyph) {
+                    Some(tile_type) => tile_type,
+                    None => {
+                        return Err(MapError::UnknownGlyph {
+                            row: row_index,
+                            column: column_index,
+                            glyph,
+                        })
+                    }
                 };
 
-                tile_index += 1;
+                tiles.push(Tile {
+                    position,
+                    tile_type,
+                });
+            }
+        }
+
+        if definition.spawns.is_empty() {
+            return Err(MapError::MissingSpawn);
+        }
+
+        if definition.exits.is_empty() {
+            return Err(MapError::MissingExit);
+        }
+
+        let map = GameMap {
+            name: definition.name,
+            background: definition.background,
+            width,
+            height,
+            spawns: definition.spawns,
+            exits: definition.exits,
+            tiles,
+            waves: None,
+            flow_fields: vec![],
+        };
+
+        let waypoints: Vec<&Waypoint> = map.spawns.iter().chain(map.exits.iter()).collect();
+
+        for (index, waypoint) in waypoints.iter().enumerate() {
+            if !map.contains(waypoint.position) {
+                return Err(MapError::WaypointOutsideOfMap {
+                    name: waypoint.name.clone(),
+                    position: waypoint.position,
+                });
+            }
+
+            if waypoints[..index].iter().any(|other| other.name == waypoint.name) {
+                return Err(MapError::DuplicateWaypoint(waypoint.name.clone()));
+            }
+        }
+
+        Ok(map)
+    }
+
+    pub fn get_spawn(&self, name: &str) -> Option<&Waypoint> {
+        self.spawns.iter().find(|spawn| spawn.name == name)
+    }
+
+    pub fn get_exit(&self, name: &str) -> Option<&Waypoint> {
+        self.exits.iter().find(|exit| exit.name == name)
+    }
+
+    /// Flow field leading to the named exit. Fields are empty until
+    /// `set_flow_fields` is called.
+    pub fn get_flow_field(&self, exit: &str) -> Option<&FlowField> {
+        self.exits
+            .iter()
+            .position(|waypoint| waypoint.name == exit)
+            .and_then(|index| self.flow_fields.get(index))
+    }
+
+    /// Computes one flow field per exit, in the same order as `exits`. Fails
+    /// when some spawn can not reach any of the exits.
+    pub fn compute_flow_fields(
+        &self,
+        walkable_tiles: &[bool],
+    ) -> Result<Vec<FlowField>, PathfindingError> {
+        let mut flow_fields = vec![];
+
+        for exit in self.exits.iter() {
+            flow_fields.push(FlowField::new(self, walkable_tiles, exit.position)?);
+        }
+
+        for spawn in self.spawns.iter() {
+            if !walkable_tiles[self.index_of(spawn.position).unwrap()] {
+                return Err(PathfindingError::SpawnNotWalkable(spawn.position));
+            }
+
+            let reaches_any_exit = flow_fields
+                .iter()
+                .any(|flow_field| flow_field.distance_from(self, spawn.position).is_some());
+
+            if !reaches_any_exit {
+                return Err(PathfindingError::NoPath {
+                    start: spawn.position,
+                    goal: self.exits[0].position,
+                });
             }
         }
 
-        GameMap { tiles_map, tiles }
+        Ok(flow_fields)
+    }
+
+    pub fn set_flow_fields(&mut self, flow_fields: Vec<FlowField>) {
+        self.flow_fields = flow_fields;
+    }
+
+    pub fn contains(&self, position: Point) -> bool {
+        position.x >= 0
+            && position.y >= 0
+            && (position.x as usize) < self.width
+            && (position.y as usize) < self.height
+    }
+
+    pub fn index_of(&self, position: Point) -> Option<usize> {
+        if !self.contains(position) {
+            return None;
+        }
+
+        Some(position.y as usize * self.width + position.x as usize)
+    }
+
+    pub fn position_of(&self, index: usize) -> Point {
+        Point::new((index % self.width) as i32, (index / self.width) as i32)
+    }
+
+    pub fn get_tile(&self, position: Point) -> Option<&Tile> {
+        self.index_of(position).map(|index| &self.tiles[index])
+    }
+
+    pub fn get_tile_type(&self, position: Point) -> Option<TileType> {
+        self.get_tile(position).map(|tile| tile.tile_type)
+    }
+
+    /// Walkability of every tile, indexed the same way as `tiles`. Open ground
+    /// counts as walkable too when monsters are allowed to leave the road.
+    pub fn get_walkable_tiles(&self, open_ground_walkable: bool) -> Vec<bool> {
+        self.tiles
+            .iter()
+            .map(|tile| {
+                tile.tile_type.is_walkable() || (open_ground_walkable && tile.tile_type.is_open_ground())
+            })
+            .collect()
     }
 
+    pub fn pixel_width(&self) -> i32 {
+        self.width as i32 * TILE_PIXEL_SIZE
+    }
+
+    pub fn pixel_height(&self) -> i32 {
+        self.height as i32 * TILE_PIXEL_SIZE
+    }
+
+    /// Draws the background image when the map has one, otherwise every
+    /// tile separately.
     pub fn get_sprites(&self) -> Vec<Sprite> {
         let mut sprites = vec![];
-        // let size = TILE_PIXEL_SIZE as u32;
-
-        sprites.push(Sprite::create_image(
-            &"/assets/tiles/map.png",
-            Point::new(0, 0),
-            735,
-            740,
-            0.0
-        ));
+        let size = TILE_PIXEL_SIZE as u32;
 
-        // for tile in self.tiles.iter() {
-        //     let mut texture_path = "/assets/tiles/".to_owned();
-        //     texture_path.push_str(if tile.tile_type == TileType::None {
-        //         "ice_1.png"
-        //     } else {
-        //         "ground_1.png"
-        //     });
-
-        //     sprites.push(Sprite::create_image(
-        //         &texture_path,
-        //         map_pos_to_pixel_pos(tile.position),
-        //         size,
-        //         size,
-        //         0.0
-        //     ));
-        // }
+        if let Some(background) = &self.background {
+            sprites.push(Sprite::create_image(
+                background,
+                Point::new(0, 0),
+                self.pixel_width() as u32,
+                self.pixel_height() as u32,
+                0.0
+            ));
+
+            return sprites;
+        }
+
+        for tile in self.tiles.iter() {
+            let position = map_pos_to_pixel_pos(tile.position);
+
+            if let Some(texture_path) = tile.tile_type.get_texture_path() {
+                sprites.push(Sprite::create_image(texture_path, position, size, size, 0.0));
+            }
+
+            if let Some(color) = tile.tile_type.get_overlay_color() {
+                sprites.push(Sprite::create_rect(color, position, size, size));
+            }
+        }
 
         sprites
     }
 }
+
+impl FromStr for GameMap {
+    type Err = MapError;
+
+    fn from_str(source: &str) -> Result<GameMap, MapError> {
+        let definition: MapDefinition = ron::Options::default()
+            .with_default_extension(Extensions::IMPLICIT_SOME)
+            .from_str(source)
+            .map_err(|error| MapError::Syntax(error.to_string()))?;
+
+        GameMap::from_definition(definition)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_map(spawns: &str, exits: &str, tiles: &[&str]) -> Result<GameMap, MapError> {
+        let rows: Vec<String> = tiles.iter().map(|row| format!("\"{}\"", row)).collect();
+
+        GameMap::from_str(&format!(
+            "(name: \"Test\", spawns: [{}], exits: [{}], tiles: [{}])",
+            spawns,
+            exits,
+            rows.join(", ")
+        ))
+    }
+
+    const SPAWN: &str = "(name: \"west\", position: (x: 0, y: 1))";
+    const EXIT: &str = "(name: \"east\", position: (x: 2, y: 1))";
+
+    #[test]
+    fn reads_tiles_and_waypoints() {
+        let map = parse_map(SPAWN, EXIT, &["...", "#%#", "^~*"]).unwrap();
+
+        assert_eq!((map.width, map.height), (3, 3));
+        assert_eq!(map.get_tile_type(Point::new(1, 1)), Some(TileType::Mud));
+        assert_eq!(map.get_tile_type(Point::new(1, 2)), Some(TileType::Water));
+        assert_eq!(map.get_spawn("west").unwrap().position, Point::new(0, 1));
+        assert_eq!(map.get_exit("east").unwrap().position, Point::new(2, 1));
+    }
+
+    #[test]
+    fn reads_builtin_maps() {
+        for (name, _) in BUILTIN_MAPS.iter() {
+            assert!(GameMap::from_name(name).is_ok(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_glyphs() {
+        let error = parse_map(SPAWN, EXIT, &["...", "#x#", "..."])
+            .err()
+            .unwrap();
+
+        assert!(matches!(
+            error,
+            MapError::UnknownGlyph {
+                row: 1,
+                column: 1,
+                glyph: 'x'
+            }
+        ));
+    }
+
+    #[test]
+    fn rejects_ragged_rows() {
+        let error = parse_map(SPAWN, EXIT, &["...", "###", ".."]).err().unwrap();
+
+        assert!(matches!(
+            error,
+            MapError::MalformedRow {
+                row: 2,
+                expected: 3,
+                found: 2
+            }
+        ));
+    }
+
+    #[test]
+    fn rejects_maps_without_tiles() {
+        let error = parse_map(SPAWN, EXIT, &[]).err().unwrap();
+
+        assert!(matches!(error, MapError::Empty));
+    }
+
+    #[test]
+    fn rejects_maps_without_spawn_or_exit() {
+        let tiles = ["...", "###", "..."];
+
+        assert!(matches!(
+            parse_map("", EXIT, &tiles).err().unwrap(),
+            MapError::MissingSpawn
+        ));
+        assert!(matches!(
+            parse_map(SPAWN, "", &tiles).err().unwrap(),
+            MapError::MissingExit
+        ));
+    }
+
+    #[test]
+    fn rejects_waypoints_outside_of_the_map() {
+        let error = parse_map(
+            SPAWN,
+            "(name: \"east\", position: (x: 3, y: 1))",
+            &["...", "###", "..."],
+        )
+        .err()
+        .unwrap();
+
+        assert!(matches!(error, MapError::WaypointOutsideOfMap { .. }));
+    }
+}
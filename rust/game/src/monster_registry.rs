@@ -0,0 +1,204 @@
+use crate::core::definitions::read_definition_sources;
+use crate::damage::Resistances;
+use crate::entities::MonsterType;
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt;
+
+const MONSTER_DEFINITION_FILE_NAME: &str = "monster.ron";
+
+/// Monsters compiled into the game, for builds that can't read the assets
+/// directory. Ids are the names of the sprite folders.
+pub const BUILTIN_MONSTERS: [(&str, &str); 2] = [
+    ("creeper", include_str!("../../assets/creatures/creeper/monster.ron")),
+    ("knight", include_str!("../../assets/creatures/knight/monster.ron")),
+];
+
+#[derive(Debug)]
+pub enum MonsterDefinitionError {
+    Io { path: String, message: String },
+    Syntax { monster: String, message: String },
+    NoHealth(String),
+    NoSpeed(String),
+    NoLivesCost(String),
+    Empty,
+}
+
+impl fmt::Display for MonsterDefinitionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MonsterDefinitionError::Io { path, message } => {
+                write!(f, "Can not read monster definitions from {}: {}", path, message)
+            }
+            MonsterDefinitionError::Syntax { monster, message } => write!(
+                f,
+                "Definition of monster \"{}\" is not valid RON: {}",
+                monster, message
+            ),
+            MonsterDefinitionError::NoHealth(monster) => {
+                write!(f, "Monster \"{}\" must have more than 0 health", monster)
+            }
+            MonsterDefinitionError::NoSpeed(monster) => {
+                write!(f, "Monster \"{}\" must have more than 0 speed", monster)
+            }
+            MonsterDefinitionError::NoLivesCost(monster) => {
+                write!(f, "Monster \"{}\" must cost at least 1 life", monster)
+            }
+            MonsterDefinitionError::Empty => write!(f, "There are no monster definitions"),
+        }
+    }
+}
+
+impl Error for MonsterDefinitionError {}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Animation {
+    /// Frames per direction, frame 0 is shown while standing.
+    pub frames: i8,
+    /// Milliseconds between walking frames.
+    pub frame_duration: f64,
+}
+
+fn default_lives_cost() -> u8 {
+    1
+}
+
+/// Contents of `assets/creatures/<id>/monster.ron`. The folder also holds the
+/// sprites, `<top|bottom|left|right>_<frame>.png` for every frame of the
+/// animation.
+#[derive(Deserialize)]
+pub struct MonsterDefinition {
+    /// Name of the folder the definition and sprites live in.
+    #[serde(skip)]
+    pub id: String,
+    pub name: String,
+    pub health: i32,
+    /// Pixels per second, a tile is `TILE_PIXEL_SIZE` pixels.
+    pub speed: f64,
+    /// Subtracted from physical damage, see `DamageType` for the others.
+    #[serde(default)]
+    pub armor: i32,
+    #[serde(default)]
+    pub resistances: Resistances,
+    pub bounty: u32,
+    /// Lives lost when the monster reaches an exit.
+    #[serde(default = "default_lives_cost")]
+    pub lives_cost: u8,
+    pub animation: Animation,
+}
+
+impl MonsterDefinition {
+    pub fn get_sprite_base_path(&self) -> String {
+        let mut path = "/assets/creatures/".to_owned();
+        path.push_str(&self.id);
+        path.push('/');
+
+        path
+    }
+
+    pub fn get_texture_paths(&self) -> Vec<String> {
+        let mut paths = vec![];
+
+        for direction in ["top", "bottom", "left", "right"].iter() {
+            for frame in 0..self.animation.frames {
+                let mut path = self.get_sprite_base_path();
+                path.push_str(direction);
+                path.push('_');
+                path.push_str(&frame.to_string());
+                path.push_str(".png");
+                paths.push(path);
+            }
+        }
+
+        paths
+    }
+}
+
+pub struct MonsterRegistry {
+    definitions: Vec<MonsterDefinition>,
+}
+
+impl MonsterRegistry {
+    pub fn builtin() -> Result<MonsterRegistry, MonsterDefinitionError> {
+        MonsterRegistry::from_sources(
+            BUILTIN_MONSTERS
+                .iter()
+                .map(|(id, source)| (id.to_string(), source.to_string()))
+                .collect(),
+        )
+    }
+
+    /// Reads `<path>/<id>/monster.ron` for every folder in `path`. Folders
+    /// without a definition are skipped.
+    pub fn from_directory(path: &str) -> Result<MonsterRegistry, MonsterDefinitionError> {
+        let sources = read_definition_sources(path, MONSTER_DEFINITION_FILE_NAME).map_err(
+            |(path, error)| MonsterDefinitionError::Io {
+                path: path.display().to_string(),
+                message: error.to_string(),
+            },
+        )?;
+
+        MonsterRegistry::from_sources(sources)
+    }
+
+    fn from_sources(
+        sources: Vec<(String, String)>,
+    ) -> Result<MonsterRegistry, MonsterDefinitionError> {
+        let mut definitions = vec![];
+
+        for (id, source) in sources {
+            let mut definition: MonsterDefinition =
+                ron::from_str(&source).map_err(|error| MonsterDefinitionError::Syntax {
+                    monster: id.clone(),
+                    message: error.to_string(),
+                })?;
+
+            if definition.health <= 0 {
+                return Err(MonsterDefinitionError::NoHealth(id));
+            }
+
+            // Endless waves divide by the threat of a monster, which is 0
+            // without speed or lives cost.
+            if definition.speed <= 0.0 {
+                return Err(MonsterDefinitionError::NoSpeed(id));
+            }
+
+            if definition.lives_cost == 0 {
+                return Err(MonsterDefinitionError::NoLivesCost(id));
+            }
+
+            definition.id = id;
+            definitions.push(definition);
+        }
+
+        if definitions.is_empty() {
+            return Err(MonsterDefinitionError::Empty);
+        }
+
+        Ok(MonsterRegistry { definitions })
+    }
+
+    /// Monster type ids are only handed out by the registry, so they always
+    /// point at a definition.
+    pub fn get(&self, monster_type: MonsterType) -> &MonsterDefinition {
+        &self.definitions[monster_type.0]
+    }
+
+    pub fn find(&self, id: &str) -> Option<MonsterType> {
+        self.definitions
+            .iter()
+            .position(|definition| definition.id == id)
+            .map(MonsterType)
+    }
+
+    pub fn get_monster_types(&self) -> Vec<MonsterType> {
+        (0..self.definitions.len()).map(MonsterType).collect()
+    }
+
+    pub fn get_texture_paths(&self) -> Vec<String> {
+        self.definitions
+            .iter()
+            .flat_map(|definition| definition.get_texture_paths())
+            .collect()
+    }
+}
@@ -1,57 +1,240 @@
 use crate::core::Point;
-use crate::map::{Tile, TileType};
-
-pub fn find_path_to_end(tiles: [Tile; 529], start: Point) -> Result<Vec<Point>, String> {
-    let movable_tiles = tiles
-        .iter()
-        .filter(|&&tile| tile.tile_type == TileType::Road);
-
-    let movable_positions: Vec<Point> = movable_tiles.into_iter().map(|x| x.position).collect();
-
-    let mut path: Vec<Point> = vec![];
-    let mut open_fields: Vec<&Point> = movable_positions
-        .iter()
-        .filter(|&&field| field != start)
-        .collect();
-
-    let mut current_working_position: Option<Point> = Some(start);
-
-    while open_fields.len() > 0 {
-        match current_working_position {
-            Some(_current_working_position) => {
-                let sibling_positions: Vec<&Point> = open_fields
-                    .iter()
-                    .filter(|x| x.is_straight_neighbour_of(&_current_working_position))
-                    .map(|&x| x)
-                    .collect();
-
-                let next_move = sibling_positions.get(0);
-
-                match next_move {
-                    Some(&&_next_move) => {
-                        path.push(_next_move);
-
-                        open_fields = open_fields
-                            .iter()
-                            .filter(|&&&field| field != _next_move)
-                            .map(|&x| x)
-                            .collect();
-
-                        current_working_position = Some(_next_move);
-                    }
-                    None => {
-                        println!("Break in match next_move");
-                        break;
-                    }
+use crate::map::GameMap;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::error::Error;
+use std::fmt;
+
+const NEIGHBOUR_OFFSETS: [Point; 4] = [
+    Point { x: 0, y: -1 },
+    Point { x: 1, y: 0 },
+    Point { x: 0, y: 1 },
+    Point { x: -1, y: 0 },
+];
+
+#[derive(Debug, PartialEq)]
+pub enum PathfindingError {
+    SpawnNotWalkable(Point),
+    GoalNotWalkable(Point),
+    NoPath { start: Point, goal: Point },
+}
+
+impl fmt::Display for PathfindingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PathfindingError::SpawnNotWalkable(position) => write!(
+                f,
+                "Spawn point ({}, {}) is not on a walkable tile",
+                position.x, position.y
+            ),
+            PathfindingError::GoalNotWalkable(position) => write!(
+                f,
+                "Goal ({}, {}) is not on a walkable tile",
+                position.x, position.y
+            ),
+            PathfindingError::NoPath { start, goal } => write!(
+                f,
+                "There is no path from ({}, {}) to ({}, {})",
+                start.x, start.y, goal.x, goal.y
+            ),
+        }
+    }
+}
+
+impl Error for PathfindingError {}
+
+fn is_walkable(map: &GameMap, walkable_tiles: &[bool], position: Point) -> bool {
+    match map.index_of(position) {
+        Some(index) => walkable_tiles[index],
+        None => false,
+    }
+}
+
+/// Cost of reaching `goal` for every tile of the map, shared by all monsters
+/// heading to the same goal. Each monster only has to look up the neighbour
+/// closest to the goal, so re-routing after the map changes is just a matter
+/// of computing the field again.
+#[derive(Clone, Debug)]
+pub struct FlowField {
+    pub goal: Point,
+    distances: Vec<Option<u32>>,
+}
+
+impl FlowField {
+    pub fn new(
+        map: &GameMap,
+        walkable_tiles: &[bool],
+        goal: Point,
+    ) -> Result<FlowField, PathfindingError> {
+        if !is_walkable(map, walkable_tiles, goal) {
+            return Err(PathfindingError::GoalNotWalkable(goal));
+        }
+
+        let goal_index = map.index_of(goal).unwrap();
+
+        let mut distances: Vec<Option<u32>> = vec![None; map.tiles.len()];
+        let mut open_fields = BinaryHeap::new();
+
+        distances[goal_index] = Some(0);
+        open_fields.push(Reverse((0, goal_index)));
+
+        while let Some(Reverse((current_distance, current_index))) = open_fields.pop() {
+            if matches!(distances[current_index], Some(distance) if distance < current_distance) {
+                continue;
+            }
+
+            let current_position = map.position_of(current_index);
+            // Monster standing on a neighbour pays for stepping onto this tile.
+            let next_distance =
+                current_distance + map.tiles[current_index].tile_type.movement_cost();
+
+            for offset in NEIGHBOUR_OFFSETS.iter() {
+                let neighbour = current_position + *offset;
+
+                if !is_walkable(map, walkable_tiles, neighbour) {
+                    continue;
+                }
+
+                let neighbour_index = map.index_of(neighbour).unwrap();
+
+                let is_shorter = match distances[neighbour_index] {
+                    Some(distance) => next_distance < distance,
+                    None => true,
+                };
+
+                if is_shorter {
+                    distances[neighbour_index] = Some(next_distance);
+                    open_fields.push(Reverse((next_distance, neighbour_index)));
                 }
             }
-            None => {
-                println!("Break in match CWP");
+        }
 
-                break;
+        Ok(FlowField { goal, distances })
+    }
+
+    /// Cost of reaching the goal, `None` when it is unreachable.
+    pub fn distance_from(&self, map: &GameMap, position: Point) -> Option<u32> {
+        map.index_of(position)
+            .and_then(|index| self.distances.get(index).copied().flatten())
+    }
+
+    /// Neighbouring tile on the cheapest way to the goal, `None` when standing
+    /// on the goal or on a tile the goal can not be reached from.
+    pub fn next_step(&self, map: &GameMap, position: Point) -> Option<Point> {
+        if self.distance_from(map, position)? == 0 {
+            return None;
+        }
+
+        let mut best_step = None;
+        let mut best_distance = u32::MAX;
+
+        for offset in NEIGHBOUR_OFFSETS.iter() {
+            let neighbour = position + *offset;
+
+            if let Some(distance) = self.distance_from(map, neighbour) {
+                let distance = distance + map.get_tile_type(neighbour).unwrap().movement_cost();
+
+                if distance < best_distance {
+                    best_distance = distance;
+                    best_step = Some(neighbour);
+                }
             }
         }
+
+        best_step
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    /// Map with a spawn in the top left and an exit in the top right corner.
+    fn create_map(tiles: &[&str]) -> GameMap {
+        let rows: Vec<String> = tiles.iter().map(|row| format!("\"{}\"", row)).collect();
+        let last_column = tiles[0].len() - 1;
+
+        GameMap::from_str(&format!(
+            "(name: \"Test\", spawns: [(name: \"west\", position: (x: 0, y: 0))], \
+             exits: [(name: \"east\", position: (x: {}, y: 0))], tiles: [{}])",
+            last_column,
+            rows.join(", ")
+        ))
+        .unwrap()
+    }
+
+    fn create_flow_field(map: &GameMap, open_ground_walkable: bool) -> FlowField {
+        FlowField::new(
+            map,
+            &map.get_walkable_tiles(open_ground_walkable),
+            map.exits[0].position,
+        )
+        .unwrap()
     }
 
-    Ok(path)
+    #[test]
+    fn counts_the_cost_of_every_tile_stepped_on() {
+        let map = create_map(&["#%#"]);
+        let flow_field = create_flow_field(&map, false);
+
+        assert_eq!(flow_field.distance_from(&map, Point::new(2, 0)), Some(0));
+        assert_eq!(flow_field.distance_from(&map, Point::new(1, 0)), Some(1));
+        assert_eq!(flow_field.distance_from(&map, Point::new(0, 0)), Some(4));
+        assert_eq!(flow_field.next_step(&map, Point::new(2, 0)), None);
+    }
+
+    #[test]
+    fn goes_around_mud_when_it_is_cheaper() {
+        let map = create_map(&["#%%%#", "#####"]);
+        let flow_field = create_flow_field(&map, false);
+
+        // Six road tiles along the bottom, through the mud would cost 10.
+        assert_eq!(flow_field.distance_from(&map, Point::new(0, 0)), Some(6));
+        assert_eq!(
+            flow_field.next_step(&map, Point::new(0, 0)),
+            Some(Point::new(0, 1))
+        );
+    }
+
+    #[test]
+    fn goes_through_mud_when_the_way_around_is_longer() {
+        let map = create_map(&["#%#", "#.#", "#.#", "###"]);
+        let flow_field = create_flow_field(&map, false);
+
+        assert_eq!(flow_field.distance_from(&map, Point::new(0, 0)), Some(4));
+        assert_eq!(
+            flow_field.next_step(&map, Point::new(0, 0)),
+            Some(Point::new(1, 0))
+        );
+    }
+
+    #[test]
+    fn never_crosses_water() {
+        let map = create_map(&["#~#", "..."]);
+
+        let flow_field = create_flow_field(&map, false);
+        assert_eq!(flow_field.distance_from(&map, Point::new(0, 0)), None);
+        assert_eq!(flow_field.next_step(&map, Point::new(0, 0)), None);
+
+        // Open ground lets monsters around it in maze mode.
+        let flow_field = create_flow_field(&map, true);
+        assert_eq!(flow_field.distance_from(&map, Point::new(0, 0)), Some(7));
+        assert_eq!(
+            flow_field.next_step(&map, Point::new(0, 0)),
+            Some(Point::new(0, 1))
+        );
+    }
+
+    #[test]
+    fn fails_when_the_goal_is_not_walkable() {
+        let map = create_map(&["#~#"]);
+        let walkable_tiles = map.get_walkable_tiles(false);
+        let goal = Point::new(1, 0);
+
+        assert_eq!(
+            FlowField::new(&map, &walkable_tiles, goal).err(),
+            Some(PathfindingError::GoalNotWalkable(goal))
+        );
+    }
 }
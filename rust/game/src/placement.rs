@@ -0,0 +1,33 @@
+use crate::map::TileType;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PlacementError {
+    OutOfBounds,
+    OnPath,
+    BlockedByTerrain(TileType),
+    Occupied,
+    OccupiedByMonster,
+    BlocksRoute,
+    NotEnoughGold { cost: u32 },
+}
+
+impl fmt::Display for PlacementError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlacementError::OutOfBounds => write!(f, "Towers can't be built outside of the map"),
+            PlacementError::OnPath => write!(f, "Towers can't be built on the path"),
+            PlacementError::BlockedByTerrain(tile_type) => {
+                let name: &str = tile_type.into();
+                write!(f, "Towers can't be built on {}", name.to_lowercase())
+            }
+            PlacementError::Occupied => write!(f, "There is already a tower here"),
+            PlacementError::OccupiedByMonster => write!(f, "A monster is in the way"),
+            PlacementError::BlocksRoute => write!(f, "Monsters would have no way to an exit"),
+            PlacementError::NotEnoughGold { cost } => write!(f, "Not enough gold, tower costs {}", cost),
+        }
+    }
+}
+
+impl Error for PlacementError {}
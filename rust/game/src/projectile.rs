@@ -1,14 +1,89 @@
-use crate::{core::{config::TILE_PIXEL_SIZE, position::map_pos_to_pixel_pos, Point, Sprite}, entities::Monster, tower::{Tower, TowerType, get_tower_sprite_base_path}};
-use std::{cell::RefMut, ops::Add};
+use crate::{core::{config::TILE_PIXEL_SIZE, Point, Sprite}, damage::DamageType, entities::Monster, status_effect::StatusEffect, tower::get_tile_center, tower_registry::TowerDefinition};
+use serde::Deserialize;
+use std::cell::RefMut;
 use uuid::Uuid;
 
+/// How far from where it is a projectile looks for a new target.
+const RETARGET_RANGE: i32 = TILE_PIXEL_SIZE * 3;
+
+/// How close to its line a piercing projectile has to pass a monster's
+/// center to hit it.
+const PIERCING_HIT_RADIUS: f64 = TILE_PIXEL_SIZE as f64 / 2.0;
+
+/// What a projectile hits once it arrives.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+pub enum ProjectileKind {
+    /// Only its target.
+    #[default]
+    Single,
+    /// Every monster within `radius` pixels of the target. Damage drops
+    /// with the distance down to `edge_damage` of it at the edge.
+    Splash { radius: i32, edge_damage: f64 },
+    /// Flies in a straight line towards where the target was when it was
+    /// shot, hitting every monster it passes, until it covered `length`
+    /// pixels. Doesn't follow the target.
+    Piercing { length: i32 },
+    /// Jumps from the target up to `jumps` times to the closest monster it
+    /// didn't hit yet within `jump_range` pixels. Every jump deals `decay`
+    /// of the damage of the one before.
+    Chain { jumps: u32, jump_range: i32, decay: f64 },
+}
+
+/// What a projectile does when its target dies before it arrives.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+pub enum TargetLost {
+    /// Disappears without hitting anything.
+    #[default]
+    Fizzle,
+    /// Flies on to the closest monster nearby, fizzles if there is none.
+    Retarget,
+}
+
+fn default_speed() -> f64 {
+    12.0
+}
+
+/// What a tower shoots, part of its definition.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ProjectileDefinition {
+    /// Pixels covered per step.
+    #[serde(default = "default_speed")]
+    pub speed: f64,
+    #[serde(default)]
+    pub damage_type: DamageType,
+    #[serde(default)]
+    pub on_target_lost: TargetLost,
+    #[serde(default)]
+    pub kind: ProjectileKind,
+}
+
+impl Default for ProjectileDefinition {
+    fn default() -> ProjectileDefinition {
+        ProjectileDefinition {
+            speed: default_speed(),
+            damage_type: DamageType::default(),
+            on_target_lost: TargetLost::default(),
+            kind: ProjectileKind::default(),
+        }
+    }
+}
+
 pub struct Projectile {
     pub position: Point,
     pub damage: i32,
+    pub damage_type: DamageType,
     pub rotation: f64,
 
     pub active: bool,
-    tower_type: TowerType,
+    texture_path: String,
+    kind: ProjectileKind,
+    effects: Vec<StatusEffect>,
+    on_target_lost: TargetLost,
+    /// Where a piercing projectile was shot from and how far it flew.
+    origin: Point,
+    distance_travelled: f64,
+    /// Monsters a piercing projectile already went through.
+    hit_monsters: Vec<Uuid>,
 
     target_position: Point,
     target_id: Uuid,
@@ -20,21 +95,33 @@ pub struct Projectile {
 impl Projectile {
     pub fn new(
         position: Point,
-        target_position: Point,
+        target: &Monster,
         damage: i32,
-        target_id: Uuid,
-        tower_type: TowerType,
+        definition: &TowerDefinition,
     ) -> Projectile {
+        let mut texture_path = definition.get_sprite_base_path();
+        texture_path.push_str("shoot.png");
+
+        let origin = get_tile_center(position);
+        let target_position = target.get_pixel_center();
+        let difference = target_position - origin;
+
         Projectile {
-            position: map_pos_to_pixel_pos(position).add(Point::new(0, 0)),
+            position: origin,
             damage,
-            rotation: 0.0,
-            target_position: map_pos_to_pixel_pos(target_position)
-                .add(Point::new(TILE_PIXEL_SIZE / 2, TILE_PIXEL_SIZE / 2)),
-            tower_type,
+            damage_type: definition.projectile.damage_type,
+            rotation: (difference.y as f64).atan2(difference.x as f64),
+            target_position,
+            kind: definition.projectile.kind,
+            effects: definition.effects.clone(),
+            on_target_lost: definition.projectile.on_target_lost,
+            origin,
+            distance_travelled: 0.0,
+            hit_monsters: vec![],
+            speed: definition.projectile.speed,
+            texture_path,
             active: true,
-            target_id,
-            speed: 12.0,
+            target_id: target.id,
             cooldown: 30.0,
             time_to_simulate: 0.0,
         }
@@ -44,7 +131,7 @@ impl Projectile {
         let mut sprites = vec![];
 
         sprites.push(Sprite::create_image(
-            &self.get_sprite_texture_path(),
+            &self.texture_path,
             self.position,
             26,
             7,
@@ -68,43 +155,215 @@ impl Projectile {
 
         self.time_to_simulate += elapsed_time;
 
-        while self.time_to_simulate >= self.cooldown {
+        while self.active && self.time_to_simulate >= self.cooldown {
             self.update_movement_single_frame(monsters);
             self.time_to_simulate -= self.cooldown;
         }
     }
 
+    /// Follows the target to where it is now. Returns `false` when the
+    /// target is gone and there is nothing else to fly to.
+    fn track_target(&mut self, monsters: &[Monster]) -> bool {
+        let target = monsters
+            .iter()
+            .find(|monster| monster.id == self.target_id && monster.is_alive());
+
+        if let Some(target) = target {
+            self.target_position = target.get_pixel_center();
+
+            return true;
+        }
+
+        if self.on_target_lost == TargetLost::Fizzle {
+            return false;
+        }
+
+        let closest = monsters
+            .iter()
+            .filter(|monster| monster.is_alive())
+            .map(|monster| {
+                let distance = get_squared_distance(monster.get_pixel_center(), self.position);
+
+                (monster, distance)
+            })
+            .filter(|(_, distance)| *distance <= RETARGET_RANGE * RETARGET_RANGE)
+            .min_by_key(|(_, distance)| *distance);
+
+        match closest {
+            Some((monster, _)) => {
+                self.target_id = monster.id;
+                self.target_position = monster.get_pixel_center();
+
+                true
+            }
+            None => false,
+        }
+    }
+
     fn update_movement_single_frame(&mut self, monsters: &mut RefMut<Vec<Monster>>) {
+        if let ProjectileKind::Piercing { length } = self.kind {
+            self.pierce(length as f64, monsters);
+
+            return;
+        }
+
+        if !self.track_target(monsters) {
+            self.active = false;
+
+            return;
+        }
+
         let distance_y_pixels = (self.target_position.y - self.position.y) as f64;
         let distance_x_pixels = (self.target_position.x - self.position.x) as f64;
 
         self.rotation = distance_y_pixels.atan2(distance_x_pixels);
 
-        let movement_x = self.rotation.cos() * self.speed;
-        let movement_y = self.rotation.sin() * self.speed;
+        // Moves at full speed until the target is within a single step, which
+        // then lands exactly on it instead of overshooting.
+        if distance_x_pixels.hypot(distance_y_pixels) > self.speed {
+            let movement_x = self.rotation.cos() * self.speed;
+            let movement_y = self.rotation.sin() * self.speed;
+
+            self.position = Point::new(
+                self.position.x + movement_x.round() as i32,
+                self.position.y + movement_y.round() as i32,
+            );
 
+            return;
+        }
+
+        self.position = self.target_position;
+
+        match self.kind {
+            ProjectileKind::Splash { radius, edge_damage } => {
+                self.splash(radius, edge_damage, monsters)
+            }
+            ProjectileKind::Chain {
+                jumps,
+                jump_range,
+                decay,
+            } => self.chain(jumps, jump_range, decay, monsters),
+            _ => {
+                if let Some(target) = monsters
+                    .iter_mut()
+                    .find(|monster| monster.id == self.target_id)
+                {
+                    self.hit(target, self.damage);
+                }
+            }
+        }
+
+        self.active = false;
+    }
+
+    fn splash(&self, radius: i32, edge_damage: f64, monsters: &mut [Monster]) {
+        for monster in monsters.iter_mut().filter(|monster| monster.is_alive()) {
+            let distance =
+                (get_squared_distance(monster.get_pixel_center(), self.position) as f64).sqrt();
+
+            if distance <= radius as f64 {
+                let falloff = 1.0 - (1.0 - edge_damage) * distance / radius as f64;
+
+                self.hit(monster, (self.damage as f64 * falloff).round() as i32);
+            }
+        }
+    }
+
+    fn chain(&self, jumps: u32, jump_range: i32, decay: f64, monsters: &mut [Monster]) {
+        let mut target = match monsters
+            .iter()
+            .position(|monster| monster.id == self.target_id)
+        {
+            Some(target) => target,
+            None => return,
+        };
+        let mut hit = vec![target];
+        let mut damage = self.damage as f64;
+
+        self.hit(&mut monsters[target], self.damage);
+
+        for _ in 0..jumps {
+            let from = monsters[target].get_pixel_center();
+
+            let next = monsters
+                .iter()
+                .enumerate()
+                .filter(|(index, monster)| !hit.contains(index) && monster.is_alive())
+                .map(|(index, monster)| {
+                    (index, get_squared_distance(monster.get_pixel_center(), from))
+                })
+                .filter(|(_, distance)| *distance <= jump_range * jump_range)
+                .min_by_key(|(_, distance)| *distance);
+
+            target = match next {
+                Some((index, _)) => index,
+                None => return,
+            };
+            damage *= decay;
+
+            hit.push(target);
+            self.hit(&mut monsters[target], damage.round() as i32);
+        }
+    }
+
+    /// Moves one step along the line and hits every monster passed on the
+    /// way, each of them once.
+    fn pierce(&mut self, length: f64, monsters: &mut [Monster]) {
+        let start = self.position;
+
+        self.distance_travelled = (self.distance_travelled + self.speed).min(length);
         self.position = Point::new(
-            self.position.x + movement_x as i32,
-            self.position.y + movement_y as i32,
+            self.origin.x + (self.rotation.cos() * self.distance_travelled).round() as i32,
+            self.origin.y + (self.rotation.sin() * self.distance_travelled).round() as i32,
         );
 
-        if (self.position.x - self.target_position.x).abs() < 5
-            && (self.position.y - self.target_position.y).abs() < 5
-        {
-            for monster in monsters.iter_mut() {
-                if monster.id == self.target_id {
-                    monster.take_damage(20);
-                }
+        for monster in monsters.iter_mut().filter(|monster| monster.is_alive()) {
+            if self.hit_monsters.contains(&monster.id) {
+                continue;
+            }
+
+            let distance =
+                get_distance_to_segment(monster.get_pixel_center(), start, self.position);
+
+            if distance <= PIERCING_HIT_RADIUS {
+                self.hit_monsters.push(monster.id);
+                self.hit(monster, self.damage);
             }
+        }
 
+        if self.distance_travelled >= length {
             self.active = false;
         }
     }
 
-    fn get_sprite_texture_path(&self) -> String {
-        let mut path = get_tower_sprite_base_path(self.tower_type.clone());
-        path.push_str("shoot.png");
+    fn hit(&self, monster: &mut Monster, damage: i32) {
+        monster.take_damage(damage, self.damage_type);
 
-        path
+        for effect in self.effects.iter() {
+            monster.apply_status_effect(*effect);
+        }
     }
 }
+
+fn get_squared_distance(a: Point, b: Point) -> i32 {
+    let difference = a - b;
+
+    difference.x * difference.x + difference.y * difference.y
+}
+
+fn get_distance_to_segment(point: Point, start: Point, end: Point) -> f64 {
+    let segment = end - start;
+    let to_point = point - start;
+    let squared_length = (segment.x * segment.x + segment.y * segment.y) as f64;
+
+    let progress = if squared_length == 0.0 {
+        0.0
+    } else {
+        ((to_point.x * segment.x + to_point.y * segment.y) as f64 / squared_length).clamp(0.0, 1.0)
+    };
+
+    let closest_x = start.x as f64 + segment.x as f64 * progress;
+    let closest_y = start.y as f64 + segment.y as f64 * progress;
+
+    (point.x as f64 - closest_x).hypot(point.y as f64 - closest_y)
+}
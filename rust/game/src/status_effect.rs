@@ -0,0 +1,245 @@
+use crate::core::Color;
+use crate::damage::{DamageType, Resistances};
+use serde::Deserialize;
+use std::fmt;
+use std::mem;
+
+/// Poison is the only effect that stacks, this caps how many applications
+/// hurt a monster at the same time.
+pub const MAX_POISON_STACKS: usize = 5;
+
+/// Timed effect a hit leaves on a monster. Durations are in ms.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub enum StatusEffect {
+    /// Moves at `factor` of the usual speed. A stronger slow replaces a
+    /// weaker one, the same one only gets refreshed.
+    Slow { factor: f64, duration: f64 },
+    /// Damage over time, every application stacks up to `MAX_POISON_STACKS`.
+    /// Not affected by resistances.
+    Poison { damage_per_second: f64, duration: f64 },
+    /// Can't move at all, a new stun refreshes the remaining time.
+    Stun { duration: f64 },
+    /// Fire damage over time. A stronger burn replaces a weaker one, the same
+    /// one only gets refreshed.
+    Burn { damage_per_second: f64, duration: f64 },
+}
+
+impl StatusEffect {
+    pub fn get_duration(&self) -> f64 {
+        match *self {
+            StatusEffect::Slow { duration, .. }
+            | StatusEffect::Poison { duration, .. }
+            | StatusEffect::Stun { duration }
+            | StatusEffect::Burn { duration, .. } => duration,
+        }
+    }
+
+    /// Speed multiplier while the effect is active.
+    pub fn get_speed_multiplier(&self) -> f64 {
+        match *self {
+            StatusEffect::Slow { factor, .. } => factor,
+            StatusEffect::Stun { .. } => 0.0,
+            _ => 1.0,
+        }
+    }
+
+    pub fn get_damage_per_second(&self) -> f64 {
+        match *self {
+            StatusEffect::Poison {
+                damage_per_second, ..
+            }
+            | StatusEffect::Burn {
+                damage_per_second, ..
+            } => damage_per_second,
+            _ => 0.0,
+        }
+    }
+
+    /// Type of the damage over time that resistances apply to.
+    pub fn get_damage_type(&self) -> Option<DamageType> {
+        match self {
+            StatusEffect::Burn { .. } => Some(DamageType::Fire),
+            _ => None,
+        }
+    }
+
+    /// Color of the indicator drawn on affected monsters.
+    pub fn get_color(&self) -> Color {
+        match self {
+            StatusEffect::Slow { .. } => Color::new(80, 160, 255, 255),
+            StatusEffect::Poison { .. } => Color::new(90, 220, 60, 255),
+            StatusEffect::Stun { .. } => Color::new(255, 230, 60, 255),
+            StatusEffect::Burn { .. } => Color::new(255, 120, 20, 255),
+        }
+    }
+
+    fn is_same_kind(&self, other: &StatusEffect) -> bool {
+        mem::discriminant(self) == mem::discriminant(other)
+    }
+
+    /// Whether `self` hurts more than `other` of the same kind.
+    fn is_stronger_than(&self, other: &StatusEffect) -> bool {
+        match (self, other) {
+            (StatusEffect::Slow { factor, .. }, StatusEffect::Slow { factor: other, .. }) => {
+                factor < other
+            }
+            _ => self.get_damage_per_second() > other.get_damage_per_second(),
+        }
+    }
+
+    fn is_as_strong_as(&self, other: &StatusEffect) -> bool {
+        self.get_speed_multiplier() == other.get_speed_multiplier()
+            && self.get_damage_per_second() == other.get_damage_per_second()
+    }
+}
+
+impl fmt::Display for StatusEffect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let seconds = self.get_duration() / 1000.0;
+
+        match *self {
+            StatusEffect::Slow { factor, .. } => write!(
+                f,
+                "Slow {}% for {}s",
+                ((1.0 - factor) * 100.0).round(),
+                seconds
+            ),
+            StatusEffect::Poison {
+                damage_per_second, ..
+            } => write!(f, "Poison {}/s for {}s", damage_per_second, seconds),
+            StatusEffect::Stun { .. } => write!(f, "Stun for {}s", seconds),
+            StatusEffect::Burn {
+                damage_per_second, ..
+            } => write!(f, "Burn {}/s for {}s", damage_per_second, seconds),
+        }
+    }
+}
+
+pub struct ActiveStatusEffect {
+    pub effect: StatusEffect,
+
+    time_left: f64,
+    /// Damage over time that didn't add up to a whole point of health yet.
+    pending_damage: f64,
+}
+
+impl ActiveStatusEffect {
+    fn new(effect: StatusEffect) -> ActiveStatusEffect {
+        ActiveStatusEffect {
+            effect,
+            time_left: effect.get_duration(),
+            pending_damage: 0.0,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.time_left > 0.0
+    }
+
+    /// Advances the effect by `elapsed_time` ms and returns the damage it
+    /// dealt in that time.
+    pub fn update(&mut self, elapsed_time: f64, resistances: &Resistances) -> i32 {
+        let elapsed_time = elapsed_time.min(self.time_left);
+        let multiplier = self
+            .effect
+            .get_damage_type()
+            .map_or(1.0, |damage_type| resistances.get_multiplier(damage_type));
+
+        self.time_left -= elapsed_time;
+        self.pending_damage +=
+            self.effect.get_damage_per_second() * multiplier * elapsed_time / 1000.0;
+
+        let damage = self.pending_damage.floor();
+        self.pending_damage -= damage;
+
+        damage as i32
+    }
+}
+
+/// Effects currently active on a monster.
+#[derive(Default)]
+pub struct StatusEffects {
+    effects: Vec<ActiveStatusEffect>,
+}
+
+impl StatusEffects {
+    pub fn apply(&mut self, effect: StatusEffect) {
+        if let StatusEffect::Poison { .. } = effect {
+            let stacks = self
+                .effects
+                .iter()
+                .filter(|active| active.effect.is_same_kind(&effect))
+                .count();
+
+            if stacks < MAX_POISON_STACKS {
+                self.effects.push(ActiveStatusEffect::new(effect));
+                return;
+            }
+
+            // Replace the stack closest to running out.
+            let oldest = self
+                .effects
+                .iter_mut()
+                .filter(|active| active.effect.is_same_kind(&effect))
+                .min_by(|a, b| a.time_left.partial_cmp(&b.time_left).unwrap());
+
+            if let Some(oldest) = oldest {
+                *oldest = ActiveStatusEffect::new(effect);
+            }
+
+            return;
+        }
+
+        let existing = self
+            .effects
+            .iter_mut()
+            .find(|active| active.effect.is_same_kind(&effect));
+
+        match existing {
+            Some(active) if effect.is_stronger_than(&active.effect) => {
+                *active = ActiveStatusEffect::new(effect);
+            }
+            Some(active) if effect.is_as_strong_as(&active.effect) => {
+                active.time_left = active.time_left.max(effect.get_duration());
+            }
+            Some(_) => {}
+            None => self.effects.push(ActiveStatusEffect::new(effect)),
+        }
+    }
+
+    /// Advances every effect by `elapsed_time` ms, drops the ones that ran
+    /// out and returns the damage they dealt.
+    pub fn update(&mut self, elapsed_time: f64, resistances: &Resistances) -> i32 {
+        let damage = self
+            .effects
+            .iter_mut()
+            .map(|active| active.update(elapsed_time, resistances))
+            .sum();
+
+        self.effects.retain(|active| active.is_active());
+
+        damage
+    }
+
+    /// The strongest slow or a stun, effects of different kinds don't add up.
+    pub fn get_speed_multiplier(&self) -> f64 {
+        self.effects
+            .iter()
+            .map(|active| active.effect.get_speed_multiplier())
+            .fold(1.0, f64::min)
+    }
+
+    /// One effect of every kind that is active, in the order they were
+    /// first applied.
+    pub fn get_kinds(&self) -> Vec<StatusEffect> {
+        let mut kinds: Vec<StatusEffect> = vec![];
+
+        for active in self.effects.iter() {
+            if !kinds.iter().any(|kind| kind.is_same_kind(&active.effect)) {
+                kinds.push(active.effect);
+            }
+        }
+
+        kinds
+    }
+}
@@ -1,46 +1,205 @@
-use crate::core::config::TILE_PIXEL_SIZE;
+use crate::core::config::{TILE_PIXEL_SIZE, TOWER_SELL_REFUND_PERCENT};
 use crate::core::position::map_pos_to_pixel_pos;
-use crate::core::{Point, Sprite};
+use crate::core::{Color, Point, Sprite};
+use crate::damage::DamageType;
 use crate::entities::Monster;
 use crate::projectile::Projectile;
+use crate::status_effect::StatusEffect;
+use crate::tower_registry::TowerDefinition;
+use serde::Deserialize;
 use std::cell::RefMut;
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
 use strum_macros::IntoStaticStr;
 
-#[derive(IntoStaticStr, Clone)]
-pub enum TowerType {
-    Orc,
+/// Id of a tower definition in the `TowerRegistry`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TowerType(pub(crate) usize);
+
+/// Whether a tower attacks or supports the ones around it.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub enum TowerBehaviour {
+    /// Shoots at monsters in range, what gets hit is up to the projectile.
+    Attack,
+    /// Doesn't attack, towers in its range deal `damage_bonus` more damage.
+    Aura { damage_bonus: f64 },
+}
+
+/// Which of the monsters in range a tower shoots at.
+#[derive(IntoStaticStr, Clone, Copy, Debug, PartialEq)]
+pub enum TargetingMode {
+    /// Closest to its exit along its path.
+    First,
+    Last,
+    Strongest,
+    Weakest,
+    Closest,
+    Fastest,
+}
+
+impl TargetingMode {
+    pub fn next(self) -> TargetingMode {
+        match self {
+            TargetingMode::First => TargetingMode::Last,
+            TargetingMode::Last => TargetingMode::Strongest,
+            TargetingMode::Strongest => TargetingMode::Weakest,
+            TargetingMode::Weakest => TargetingMode::Closest,
+            TargetingMode::Closest => TargetingMode::Fastest,
+            TargetingMode::Fastest => TargetingMode::First,
+        }
+    }
+}
+
+/// Stats of a tower at a given level. `cost` is what building (level 1) or
+/// upgrading to the level charges.
+#[derive(Clone, Debug, Deserialize)]
+pub struct TowerLevel {
+    pub cost: u32,
+    pub damage: i32,
+    /// In tiles, measured from the center of the tower's tile.
+    pub range: f64,
+    /// Time in ms between two attacks.
+    pub attack_cooldown: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TowerError {
+    NotFound,
+    MaxLevel,
+    NotEnoughGold { cost: u32 },
 }
 
+impl fmt::Display for TowerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TowerError::NotFound => write!(f, "There is no tower here"),
+            TowerError::MaxLevel => write!(f, "Tower is already at max level"),
+            TowerError::NotEnoughGold { cost } => {
+                write!(f, "Not enough gold, upgrade costs {}", cost)
+            }
+        }
+    }
+}
+
+impl Error for TowerError {}
+
 pub struct Tower {
     pub position: Point,
     pub level: i8,
     pub tower_type: TowerType,
+    pub targeting_mode: TargetingMode,
 
+    definition: Rc<TowerDefinition>,
     time_from_last_attack: f64,
-    range: i32,
+    range: f64,
     attack_cooldown: f64,
     damage: i32,
+    damage_bonus: f64,
 }
 
 impl Tower {
-    pub fn new(position: Point, tower_type: TowerType) -> Tower {
+    pub fn new(position: Point, tower_type: TowerType, definition: Rc<TowerDefinition>) -> Tower {
+        let stats = definition.get_level(1).unwrap().clone();
+
         Tower {
+            definition,
             position,
             level: 1,
             time_from_last_attack: 0.0,
-            range: 2,
-            attack_cooldown: 500.0,
-            damage: 20,
-            tower_type
+            range: stats.range,
+            attack_cooldown: stats.attack_cooldown,
+            damage: stats.damage,
+            damage_bonus: 0.0,
+            tower_type,
+            targeting_mode: TargetingMode::First,
         }
     }
 
-    fn get_sprite_texture_path(&self) -> String {
-        let mut path = get_tower_sprite_base_path(self.tower_type.clone());
+    pub fn get_name(&self) -> &str {
+        &self.definition.name
+    }
+
+    pub fn get_behaviour(&self) -> TowerBehaviour {
+        self.definition.behaviour
+    }
+
+    pub fn get_damage_type(&self) -> DamageType {
+        self.definition.projectile.damage_type
+    }
+
+    pub fn get_effects(&self) -> &[StatusEffect] {
+        &self.definition.effects
+    }
+
+    pub fn cycle_targeting_mode(&mut self) {
+        self.targeting_mode = self.targeting_mode.next();
+    }
+
+    /// Damage including the bonus from nearby support towers.
+    pub fn get_damage(&self) -> i32 {
+        (self.damage as f64 * (1.0 + self.damage_bonus)).round() as i32
+    }
+
+    pub fn get_range(&self) -> f64 {
+        self.range
+    }
+
+    /// Center of the tile the tower stands on, which range is measured from.
+    pub fn get_center(&self) -> Point {
+        get_tile_center(self.position)
+    }
+
+    pub fn get_range_in_pixels(&self) -> i32 {
+        range_to_pixels(self.range)
+    }
+
+    pub fn get_attack_cooldown(&self) -> f64 {
+        self.attack_cooldown
+    }
+
+    /// Gold needed for the next level, `None` at max level.
+    pub fn get_upgrade_cost(&self) -> Option<u32> {
+        self.definition
+            .get_level(self.level + 1)
+            .map(|level| level.cost)
+    }
+
+    /// Gold spent on building the tower and on all of its upgrades.
+    pub fn get_invested_gold(&self) -> u32 {
+        self.definition.levels[..self.level as usize]
+            .iter()
+            .map(|level| level.cost)
+            .sum()
+    }
+
+    pub fn get_sell_refund(&self) -> u32 {
+        self.get_invested_gold() * TOWER_SELL_REFUND_PERCENT / 100
+    }
+
+    pub fn upgrade(&mut self) -> Result<(), TowerError> {
+        let stats = self
+            .definition
+            .get_level(self.level + 1)
+            .ok_or(TowerError::MaxLevel)?
+            .clone();
+
+        self.level += 1;
+        self.damage = stats.damage;
+        self.range = stats.range;
+        self.attack_cooldown = stats.attack_cooldown;
+
+        Ok(())
+    }
+
+    fn get_sprite_texture_path(&self, file_name: &str) -> String {
+        let mut path = self.definition.get_sprite_base_path();
 
         path.push_str("level ");
         path.push_str(&self.level.to_string());
-        path.push_str("/full.png");
+        path.push('/');
+        path.push_str(file_name);
 
         path
     }
@@ -48,22 +207,41 @@ impl Tower {
     pub fn get_sprites(&self) -> Vec<Sprite> {
         let mut sprites = vec![];
 
-        let x_pixels: i8 = -32;
-        let y_pixels: i8 = -32;
-
         let position = map_pos_to_pixel_pos(self.position);
-        let position = Point {
-            x: position.x + x_pixels as i32,
-            y: position.y + y_pixels as i32,
-        };
 
-        sprites.push(Sprite::create_image(
-            &self.get_sprite_texture_path(),
-            position,
-            TILE_PIXEL_SIZE as u32 * 2,
-            TILE_PIXEL_SIZE as u32 * 2,
-            0.0,
-        ));
+        // Only the first level comes as a single image, the others are split
+        // into four tiles with `main` being the one the tower stands on.
+        if self.level == 1 {
+            sprites.push(Sprite::create_image(
+                &self.get_sprite_texture_path("full.png"),
+                Point {
+                    x: position.x - TILE_PIXEL_SIZE,
+                    y: position.y - TILE_PIXEL_SIZE,
+                },
+                TILE_PIXEL_SIZE as u32 * 2,
+                TILE_PIXEL_SIZE as u32 * 2,
+                0.0,
+            ));
+
+            return sprites;
+        }
+
+        let quadrants = [
+            ("ul.png", Point::new(-TILE_PIXEL_SIZE, -TILE_PIXEL_SIZE)),
+            ("u.png", Point::new(0, -TILE_PIXEL_SIZE)),
+            ("l.png", Point::new(-TILE_PIXEL_SIZE, 0)),
+            ("main.png", Point::new(0, 0)),
+        ];
+
+        for (file_name, offset) in quadrants.iter() {
+            sprites.push(Sprite::create_image(
+                &self.get_sprite_texture_path(file_name),
+                position + *offset,
+                TILE_PIXEL_SIZE as u32,
+                TILE_PIXEL_SIZE as u32,
+                0.0,
+            ));
+        }
 
         sprites
     }
@@ -74,22 +252,69 @@ impl Tower {
         monsters: &mut RefMut<Vec<Monster>>,
         projectiles: &mut RefMut<Vec<Projectile>>,
     ) {
+        if let TowerBehaviour::Aura { .. } = self.get_behaviour() {
+            return;
+        }
+
         self.time_from_last_attack += elapsed_time;
 
         if self.attack_cooldown <= self.time_from_last_attack {
-            for entity in monsters.iter_mut() {
-                let abs_x_diff = (entity.position.x - self.position.x).abs();
-                let abs_y_diff = (entity.position.y - self.position.y).abs();
-
-                if abs_x_diff <= self.range && abs_y_diff <= self.range {
-                    self.send_projectile_towards_creature(entity, projectiles);
-                    self.time_from_last_attack = 0.0;
-                    break;
-                }
+            let target = monsters
+                .iter_mut()
+                .filter(|entity| entity.is_alive() && self.is_in_range(entity))
+                .max_by(|a, b| self.compare_targets(a, b));
+
+            if let Some(entity) = target {
+                self.send_projectile_towards_creature(entity, projectiles);
+                self.time_from_last_attack = 0.0;
             }
         }
     }
 
+    fn covers(&self, position: Point) -> bool {
+        let difference = get_tile_center(position) - self.get_center();
+        let range = self.get_range_in_pixels();
+
+        difference.x * difference.x + difference.y * difference.y <= range * range
+    }
+
+    fn is_in_range(&self, monster: &Monster) -> bool {
+        let range = self.get_range_in_pixels();
+
+        self.get_squared_distance_to(monster) <= range * range
+    }
+
+    /// Between the tower's center and the center of the monster as drawn,
+    /// so monsters walking between tiles are measured where they are.
+    fn get_squared_distance_to(&self, monster: &Monster) -> i32 {
+        let difference = monster.get_pixel_center() - self.get_center();
+
+        difference.x * difference.x + difference.y * difference.y
+    }
+
+    /// `Greater` when `a` is the better target for the current mode.
+    fn compare_targets(&self, a: &Monster, b: &Monster) -> Ordering {
+        match self.targeting_mode {
+            TargetingMode::First => b
+                .get_distance_to_exit()
+                .partial_cmp(&a.get_distance_to_exit())
+                .unwrap_or(Ordering::Equal),
+            TargetingMode::Last => a
+                .get_distance_to_exit()
+                .partial_cmp(&b.get_distance_to_exit())
+                .unwrap_or(Ordering::Equal),
+            TargetingMode::Strongest => a.health.cmp(&b.health),
+            TargetingMode::Weakest => b.health.cmp(&a.health),
+            TargetingMode::Closest => self
+                .get_squared_distance_to(b)
+                .cmp(&self.get_squared_distance_to(a)),
+            TargetingMode::Fastest => a
+                .get_speed()
+                .partial_cmp(&b.get_speed())
+                .unwrap_or(Ordering::Equal),
+        }
+    }
+
     fn send_projectile_towards_creature(
         &self,
         creature: &mut Monster,
@@ -97,10 +322,9 @@ impl Tower {
     ) {
         let projectile = Projectile::new(
             self.position,
-            creature.position,
-            self.damage,
-            creature.id,
-            self.tower_type.clone(),
+            creature,
+            self.get_damage(),
+            &self.definition,
         );
         // if (this.takeMonsterHealthBeforeReaching) {
         //   this.dealDamageToCreature(creature);
@@ -113,14 +337,48 @@ impl Tower {
     }
 }
 
-pub fn get_tower_sprite_base_path(tower_type: TowerType) -> String {
-    let mut path = "/assets/towers/".to_owned();
+/// Sums up the damage bonus every tower gets from support towers in range.
+pub fn apply_support_auras(towers: &mut [Tower]) {
+    let bonuses: Vec<f64> = towers
+        .iter()
+        .map(|tower| {
+            towers
+                .iter()
+                .filter(|support| support.position != tower.position && support.covers(tower.position))
+                .filter_map(|support| match support.get_behaviour() {
+                    TowerBehaviour::Aura { damage_bonus } => Some(damage_bonus),
+                    _ => None,
+                })
+                .sum()
+        })
+        .collect();
 
-    let name: &str = tower_type.into();
-    let name = name.to_lowercase();
+    for (tower, bonus) in towers.iter_mut().zip(bonuses) {
+        tower.damage_bonus = bonus;
+    }
+}
+
+pub fn range_to_pixels(range: f64) -> i32 {
+    (range * TILE_PIXEL_SIZE as f64).round() as i32
+}
+
+pub fn get_tile_center(position: Point) -> Point {
+    map_pos_to_pixel_pos(position) + Point::new(TILE_PIXEL_SIZE / 2, TILE_PIXEL_SIZE / 2)
+}
+
+pub fn get_range_sprite(position: Point, range: f64) -> Sprite {
+    Sprite::create_circle(
+        Color::new(255, 255, 255, 40),
+        get_tile_center(position),
+        range_to_pixels(range) as u32,
+    )
+}
+
+pub fn get_tower_sprite_base_path(tower_id: &str) -> String {
+    let mut path = "/assets/towers/".to_owned();
 
-    path.push_str(&name);
-    path.push_str("/");
+    path.push_str(tower_id);
+    path.push('/');
 
     path
 }
@@ -1,49 +1,94 @@
 use std::ops::Add;
+use std::rc::Rc;
 
 use crate::{
     core::{
         config::TILE_PIXEL_SIZE,
-        position::{pixel_pos_to_map_pos, Rectangle},
+        position::Rectangle,
         Color, Point, Sprite,
     },
     game::MouseState,
     game_commands::GameCommand,
-    tower::{get_tower_sprite_base_path, TowerType},
+    placement::PlacementError,
+    tower::{get_range_sprite, TowerType},
+    tower_registry::TowerRegistry,
 };
 
-const TOWERS: [TowerType; 1] = [TowerType::Orc];
+const SLOTS_PER_ROW: usize = 4;
+const SLOT_SIZE: i32 = 32;
+const SLOT_SPACING: Point = Point { x: 48, y: 52 };
 
 pub struct TowerBuilder {
     mouse_state: MouseState,
     ui_start_position: Point,
     chosen_tower: Option<TowerType>,
+    placement: Option<(Point, Result<(), PlacementError>)>,
+    gold: u32,
+    tower_registry: Rc<TowerRegistry>,
 }
 
 impl TowerBuilder {
-    pub fn new(ui_start_position: Point) -> TowerBuilder {
+    pub fn new(ui_start_position: Point, tower_registry: Rc<TowerRegistry>) -> TowerBuilder {
         TowerBuilder {
             mouse_state: MouseState::new(false, Point::new(0, 0)),
             ui_start_position,
             chosen_tower: None,
+            placement: None,
+            gold: 0,
+            tower_registry,
         }
     }
 
+    pub fn is_building(&self) -> bool {
+        self.chosen_tower.is_some()
+    }
+
+    pub fn get_chosen_tower(&self) -> Option<&TowerType> {
+        self.chosen_tower.as_ref()
+    }
+
+    pub fn set_gold(&mut self, gold: u32) {
+        self.gold = gold;
+    }
+
+    fn can_afford(&self, tower_type: &TowerType) -> bool {
+        self.gold >= self.tower_registry.get(*tower_type).get_cost(1)
+    }
+
+    /// Result of validating the tile under the cursor, used for the preview
+    /// and to decide whether clicking builds the chosen tower.
+    pub fn set_placement(&mut self, position: Point, placement: Result<(), PlacementError>) {
+        self.placement = Some((position, placement));
+    }
+
     pub fn update(&mut self, mouse_state: MouseState) -> Option<GameCommand> {
         let previous_mouse_state = self.mouse_state.clone();
         self.mouse_state = mouse_state;
 
         if self.mouse_state.left_button_pressed && !previous_mouse_state.left_button_pressed {
-            if let Some(tower_type) = self.chosen_tower.clone() {
-                self.chosen_tower = None;
-
-                return Some(GameCommand::BuildTower {
-                    tower_type,
-                    position: pixel_pos_to_map_pos(self.mouse_state.position),
-                });
+            if let Some(tower_type) = self.chosen_tower {
+                // Clicking outside of the map cancels building, clicking on a
+                // tile the tower can't stand on keeps it chosen.
+                match self.placement.take() {
+                    Some((position, Ok(()))) => {
+                        self.chosen_tower = None;
+
+                        return Some(GameCommand::BuildTower {
+                            tower_type,
+                            position,
+                        });
+                    }
+                    Some((_, Err(PlacementError::OutOfBounds))) => {
+                        self.chosen_tower = None;
+                    }
+                    _ => {}
+                }
             } else {
                 for (tower_type, rect) in self.get_towers_rectangles().iter() {
-                    if Rectangle::from(self.mouse_state.position).intersects(rect.clone()) {
-                        self.chosen_tower = Some(tower_type.clone());
+                    if Rectangle::from(self.mouse_state.position).intersects(rect.clone())
+                        && self.can_afford(tower_type)
+                    {
+                        self.chosen_tower = Some(*tower_type);
                     }
                 }
             }
@@ -57,8 +102,9 @@ impl TowerBuilder {
 
         sprites.append(&mut self.get_towers_builder_menu());
 
-        if let Some(tower_type) = &self.chosen_tower {
-            let mut path = get_tower_sprite_base_path(tower_type.clone());
+        if let Some(tower_type) = self.chosen_tower {
+            let definition = self.tower_registry.get(tower_type);
+            let mut path = definition.get_sprite_base_path();
             path.push_str("level 1/icon.png");
             sprites.push(Sprite::create_image(
                 &path,
@@ -68,16 +114,38 @@ impl TowerBuilder {
                 0.0,
             ));
 
-            sprites.push(Sprite::create_rect(
-                Color::new(255, 255, 255, 30),
-                pixel_pos_to_map_pos(self.mouse_state.position)
-                    * Point {
-                        x: TILE_PIXEL_SIZE,
-                        y: TILE_PIXEL_SIZE,
-                    },
-                TILE_PIXEL_SIZE as u32,
-                TILE_PIXEL_SIZE as u32,
-            ));
+            match &self.placement {
+                Some((_, Err(PlacementError::OutOfBounds))) | None => {}
+                Some((position, placement)) => {
+                    if let Some(level) = definition.get_level(1) {
+                        sprites.push(get_range_sprite(*position, level.range));
+                    }
+
+                    let color = match placement {
+                        Ok(()) => Color::new(0, 255, 0, 60),
+                        Err(_) => Color::new(255, 0, 0, 60),
+                    };
+
+                    sprites.push(Sprite::create_rect(
+                        color,
+                        *position
+                            * Point {
+                                x: TILE_PIXEL_SIZE,
+                                y: TILE_PIXEL_SIZE,
+                            },
+                        TILE_PIXEL_SIZE as u32,
+                        TILE_PIXEL_SIZE as u32,
+                    ));
+
+                    if let Err(error) = placement {
+                        sprites.push(Sprite::create_text(
+                            &error.to_string(),
+                            self.mouse_state.position.add(Point::new(0, 32)),
+                            16,
+                        ));
+                    }
+                }
+            }
         }
 
         sprites
@@ -85,27 +153,27 @@ impl TowerBuilder {
 
     pub fn get_towers_builder_menu(&self) -> Vec<Sprite> {
         let mut sprites = vec![];
+        let mut hovered_tower = None;
 
-        for tower_type in TOWERS.iter() {
-            let mut path = get_tower_sprite_base_path(tower_type.clone());
+        for (tower_type, rect) in self.get_towers_rectangles() {
+            let definition = self.tower_registry.get(tower_type);
+            let mut path = definition.get_sprite_base_path();
             path.push_str("level 1/icon.png");
 
             sprites.push(Sprite::create_image(
-                &"/assets/interface/slot.png",
-                self.ui_start_position,
-                32,
-                32,
+                "/assets/interface/slot.png",
+                rect.start,
+                SLOT_SIZE as u32,
+                SLOT_SIZE as u32,
                 0.0,
             ));
 
-            if Rectangle::from(self.mouse_state.position).intersects(Rectangle::new(
-                self.ui_start_position,
-                32,
-                32,
-            )) {
+            if Rectangle::from(self.mouse_state.position).intersects(rect.clone()) {
+                hovered_tower = Some(tower_type);
+
                 sprites.push(Sprite::create_rect(
                     Color::new(255, 255, 255, 30),
-                    self.ui_start_position,
+                    rect.start,
                     30,
                     30,
                 ));
@@ -113,26 +181,63 @@ impl TowerBuilder {
 
             sprites.push(Sprite::create_image(
                 &path,
-                self.ui_start_position.add(Point::new(-2, -2)),
+                rect.start.add(Point::new(-2, -2)),
                 30,
                 30,
                 0.0,
             ));
-        }
 
-        sprites
-    }
+            if !self.can_afford(&tower_type) {
+                sprites.push(Sprite::create_rect(
+                    Color::new(0, 0, 0, 150),
+                    rect.start,
+                    SLOT_SIZE as u32,
+                    SLOT_SIZE as u32,
+                ));
+            }
 
-    fn get_towers_rectangles(&self) -> Vec<(TowerType, Rectangle)> {
-        let mut rectangles = vec![];
+            sprites.push(Sprite::create_text(
+                &definition.get_cost(1).to_string(),
+                rect.start.add(Point::new(0, SLOT_SIZE + 2)),
+                8,
+            ));
+        }
 
-        for tower_type in TOWERS.iter() {
-            rectangles.push((
-                tower_type.clone(),
-                Rectangle::new(self.ui_start_position, 32, 32),
+        if let Some(tower_type) = hovered_tower {
+            let towers = self.tower_registry.get_tower_types().len();
+            let rows = towers.div_ceil(SLOTS_PER_ROW);
+
+            sprites.push(Sprite::create_text(
+                &self.tower_registry.get(tower_type).name,
+                self.ui_start_position
+                    .add(Point::new(0, rows as i32 * SLOT_SPACING.y)),
+                16,
             ));
         }
 
-        rectangles
+        sprites
+    }
+
+    fn get_towers_rectangles(&self) -> Vec<(TowerType, Rectangle)> {
+        self.tower_registry
+            .get_tower_types()
+            .into_iter()
+            .enumerate()
+            .map(|(index, tower_type)| {
+                let slot = Point::new(
+                    (index % SLOTS_PER_ROW) as i32,
+                    (index / SLOTS_PER_ROW) as i32,
+                );
+
+                (
+                    tower_type,
+                    Rectangle::new(
+                        self.ui_start_position.add(slot * SLOT_SPACING),
+                        SLOT_SIZE,
+                        SLOT_SIZE,
+                    ),
+                )
+            })
+            .collect()
     }
 }
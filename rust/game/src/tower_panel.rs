@@ -0,0 +1,268 @@
+use std::ops::Add;
+
+use crate::{
+    core::{
+        config::TILE_PIXEL_SIZE,
+        position::{map_pos_to_pixel_pos, pixel_pos_to_map_pos, Rectangle},
+        Color, Point, Sprite,
+    },
+    game::{KeyboardShortcut, MouseState},
+    game_commands::GameCommand,
+    tower::{get_range_sprite, Tower, TowerBehaviour},
+};
+
+const BUTTON_WIDTH: i32 = 120;
+const BUTTON_HEIGHT: i32 = 24;
+
+/// Details of the tower the player clicked on, with the actions available
+/// for it.
+pub struct TowerPanel {
+    mouse_state: MouseState,
+    ui_start_position: Point,
+    map_area: Rectangle,
+    selected_tower: Option<Point>,
+    gold: u32,
+}
+
+impl TowerPanel {
+    pub fn new(ui_start_position: Point, map_area: Rectangle) -> TowerPanel {
+        TowerPanel {
+            mouse_state: MouseState::new(false, Point::new(0, 0)),
+            ui_start_position,
+            map_area,
+            selected_tower: None,
+            gold: 0,
+        }
+    }
+
+    pub fn get_selected_tower(&self) -> Option<Point> {
+        self.selected_tower
+    }
+
+    pub fn set_gold(&mut self, gold: u32) {
+        self.gold = gold;
+    }
+
+    fn get_upgrade_button_rectangle(&self) -> Rectangle {
+        Rectangle::new(
+            self.ui_start_position.add(Point::new(0, 100)),
+            BUTTON_WIDTH,
+            BUTTON_HEIGHT,
+        )
+    }
+
+    fn get_sell_button_rectangle(&self) -> Rectangle {
+        Rectangle::new(
+            self.ui_start_position.add(Point::new(0, 130)),
+            BUTTON_WIDTH,
+            BUTTON_HEIGHT,
+        )
+    }
+
+    fn get_targeting_button_rectangle(&self) -> Rectangle {
+        Rectangle::new(
+            self.ui_start_position.add(Point::new(0, 160)),
+            BUTTON_WIDTH,
+            BUTTON_HEIGHT,
+        )
+    }
+
+    pub fn handle_shortcut(&self, shortcut: KeyboardShortcut) -> Option<GameCommand> {
+        let position = self.selected_tower?;
+
+        match shortcut {
+            KeyboardShortcut::UpgradeTower => Some(GameCommand::UpgradeTower { position }),
+            KeyboardShortcut::SellTower => Some(GameCommand::SellTower { position }),
+            KeyboardShortcut::CycleTargetingMode => {
+                Some(GameCommand::CycleTargetingMode { position })
+            }
+            _ => None,
+        }
+    }
+
+    pub fn update(&mut self, mouse_state: MouseState, towers: &[Tower]) -> Option<GameCommand> {
+        let previous_mouse_state = self.mouse_state.clone();
+        self.mouse_state = mouse_state;
+
+        if let Some(position) = self.selected_tower {
+            if !towers.iter().any(|tower| tower.position == position) {
+                self.selected_tower = None;
+            }
+        }
+
+        if !self.mouse_state.left_button_pressed || previous_mouse_state.left_button_pressed {
+            return None;
+        }
+
+        let cursor = Rectangle::from(self.mouse_state.position);
+
+        if let Some(position) = self.selected_tower {
+            if cursor.intersects(self.get_upgrade_button_rectangle()) {
+                return Some(GameCommand::UpgradeTower { position });
+            }
+
+            if cursor.intersects(self.get_sell_button_rectangle()) {
+                return Some(GameCommand::SellTower { position });
+            }
+
+            if cursor.intersects(self.get_targeting_button_rectangle()) {
+                return Some(GameCommand::CycleTargetingMode { position });
+            }
+        }
+
+        // Clicking on the map selects the tower under the cursor, or closes
+        // the panel when there is none.
+        if cursor.intersects(self.map_area.clone()) {
+            let position = pixel_pos_to_map_pos(self.mouse_state.position);
+
+            self.selected_tower = towers
+                .iter()
+                .find(|tower| tower.position == position)
+                .map(|tower| tower.position);
+        }
+
+        None
+    }
+
+    pub fn get_sprites(&self, tower: &Tower) -> Vec<Sprite> {
+        let mut sprites = vec![];
+
+        sprites.push(get_range_sprite(tower.position, tower.get_range()));
+
+        sprites.push(Sprite::create_rect(
+            Color::new(255, 255, 255, 40),
+            map_pos_to_pixel_pos(tower.position),
+            TILE_PIXEL_SIZE as u32,
+            TILE_PIXEL_SIZE as u32,
+        ));
+
+        let mut title = tower.get_name().to_string();
+        title.push_str(" - level ");
+        title.push_str(&tower.level.to_string());
+
+        sprites.push(Sprite::create_text(&title, self.ui_start_position, 16));
+
+        let damage = match tower.get_behaviour() {
+            TowerBehaviour::Aura { damage_bonus } => {
+                ("Aura: ", format!("+{}% damage", (damage_bonus * 100.0).round()))
+            }
+            _ => {
+                let damage_type: &str = tower.get_damage_type().into();
+
+                ("Damage: ", format!("{} {}", tower.get_damage(), damage_type))
+            }
+        };
+
+        let stats = [
+            damage,
+            ("Range: ", format!("{:.1}", tower.get_range())),
+            (
+                "Attacks/s: ",
+                format!("{:.1}", 1000.0 / tower.get_attack_cooldown()),
+            ),
+        ];
+
+        for (index, (label, value)) in stats.iter().enumerate() {
+            let mut text = label.to_string();
+            text.push_str(value);
+
+            sprites.push(Sprite::create_text(
+                &text,
+                self.ui_start_position.add(Point::new(0, 24 + index as i32 * 20)),
+                16,
+            ));
+        }
+
+        let effects: Vec<String> = tower
+            .get_effects()
+            .iter()
+            .map(|effect| effect.to_string())
+            .collect();
+
+        if !effects.is_empty() {
+            sprites.push(Sprite::create_text(
+                &effects.join(", "),
+                self.ui_start_position.add(Point::new(0, 84)),
+                8,
+            ));
+        }
+
+        match tower.get_upgrade_cost() {
+            Some(cost) => {
+                let mut label = "Upgrade: ".to_string();
+                label.push_str(&cost.to_string());
+
+                sprites.append(&mut self.get_button_sprites(
+                    &label,
+                    self.get_upgrade_button_rectangle(),
+                    self.gold >= cost,
+                ));
+            }
+            None => sprites.append(&mut self.get_button_sprites(
+                "Max level",
+                self.get_upgrade_button_rectangle(),
+                false,
+            )),
+        }
+
+        let mut label = "Sell: ".to_string();
+        label.push_str(&tower.get_sell_refund().to_string());
+
+        sprites.append(&mut self.get_button_sprites(
+            &label,
+            self.get_sell_button_rectangle(),
+            true,
+        ));
+
+        let mode: &str = tower.targeting_mode.into();
+        let mut label = "Target: ".to_string();
+        label.push_str(mode);
+
+        sprites.append(&mut self.get_button_sprites(
+            &label,
+            self.get_targeting_button_rectangle(),
+            true,
+        ));
+
+        sprites.push(Sprite::create_text(
+            "[U] upgrade  [S] sell  [T] target",
+            self.ui_start_position.add(Point::new(0, 192)),
+            8,
+        ));
+
+        sprites.push(Sprite::create_text(
+            "Selling is immediate, no confirmation",
+            self.ui_start_position.add(Point::new(0, 204)),
+            8,
+        ));
+
+        sprites
+    }
+
+    fn get_button_sprites(&self, label: &str, rectangle: Rectangle, enabled: bool) -> Vec<Sprite> {
+        let mut sprites = vec![];
+
+        let color = if !enabled {
+            Color::new(60, 60, 60, 200)
+        } else if Rectangle::from(self.mouse_state.position).intersects(rectangle.clone()) {
+            Color::new(120, 90, 40, 230)
+        } else {
+            Color::new(90, 65, 30, 230)
+        };
+
+        sprites.push(Sprite::create_rect(
+            color,
+            rectangle.start,
+            rectangle.width as u32,
+            rectangle.height as u32,
+        ));
+
+        sprites.push(Sprite::create_text(
+            label,
+            rectangle.start.add(Point::new(6, 4)),
+            16,
+        ));
+
+        sprites
+    }
+}
@@ -0,0 +1,190 @@
+use crate::core::definitions::read_definition_sources;
+use crate::projectile::ProjectileDefinition;
+use crate::status_effect::StatusEffect;
+use crate::tower::{get_tower_sprite_base_path, TowerBehaviour, TowerLevel, TowerType};
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
+
+const TOWER_DEFINITION_FILE_NAME: &str = "tower.ron";
+
+/// Towers compiled into the game, for builds that can't read the assets
+/// directory. Ids are the names of the sprite folders.
+pub const BUILTIN_TOWERS: [(&str, &str); 7] = [
+    ("archer", include_str!("../../assets/towers/archer/tower.ron")),
+    ("cannon", include_str!("../../assets/towers/cannon/tower.ron")),
+    ("frost", include_str!("../../assets/towers/frost/tower.ron")),
+    ("orc", include_str!("../../assets/towers/orc/tower.ron")),
+    ("sniper", include_str!("../../assets/towers/sniper/tower.ron")),
+    ("storm", include_str!("../../assets/towers/storm/tower.ron")),
+    ("support", include_str!("../../assets/towers/support/tower.ron")),
+];
+
+#[derive(Debug)]
+pub enum TowerDefinitionError {
+    Io { path: String, message: String },
+    Syntax { tower: String, message: String },
+    NoLevels(String),
+    Empty,
+}
+
+impl fmt::Display for TowerDefinitionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TowerDefinitionError::Io { path, message } => {
+                write!(f, "Can not read tower definitions from {}: {}", path, message)
+            }
+            TowerDefinitionError::Syntax { tower, message } => {
+                write!(f, "Definition of tower \"{}\" is not valid RON: {}", tower, message)
+            }
+            TowerDefinitionError::NoLevels(tower) => {
+                write!(f, "Tower \"{}\" has no levels, add at least one to \"levels\"", tower)
+            }
+            TowerDefinitionError::Empty => write!(f, "There are no tower definitions"),
+        }
+    }
+}
+
+impl Error for TowerDefinitionError {}
+
+/// Contents of `assets/towers/<id>/tower.ron`. The folder also holds the
+/// sprites: `level 1/full.png` and `level 1/icon.png`, `ul.png`, `u.png`,
+/// `l.png` and `main.png` in `level <n>/` for every further level, and
+/// `shoot.png` for the projectile. `effects` and `projectile` are optional,
+/// towers shoot single target physical projectiles by default.
+#[derive(Deserialize)]
+pub struct TowerDefinition {
+    /// Name of the folder the definition and sprites live in.
+    #[serde(skip)]
+    pub id: String,
+    pub name: String,
+    pub behaviour: TowerBehaviour,
+    /// Applied to every monster the tower's projectiles hit.
+    #[serde(default)]
+    pub effects: Vec<StatusEffect>,
+    #[serde(default)]
+    pub projectile: ProjectileDefinition,
+    pub levels: Vec<TowerLevel>,
+}
+
+impl TowerDefinition {
+    pub fn get_level(&self, level: i8) -> Option<&TowerLevel> {
+        if level < 1 {
+            return None;
+        }
+
+        self.levels.get(level as usize - 1)
+    }
+
+    /// Gold needed to build the tower (level 1) or to upgrade it to `level`.
+    pub fn get_cost(&self, level: i8) -> u32 {
+        self.get_level(level).map_or(0, |level| level.cost)
+    }
+
+    pub fn get_sprite_base_path(&self) -> String {
+        get_tower_sprite_base_path(&self.id)
+    }
+
+    /// Every texture the tower and its projectiles can be drawn with.
+    pub fn get_texture_paths(&self) -> Vec<String> {
+        let base_path = self.get_sprite_base_path();
+        let mut paths = vec![];
+
+        for file_name in ["level 1/full.png", "level 1/icon.png", "shoot.png"].iter() {
+            let mut path = base_path.clone();
+            path.push_str(file_name);
+            paths.push(path);
+        }
+
+        for level in 2..=self.levels.len() {
+            for file_name in ["ul.png", "u.png", "l.png", "main.png"].iter() {
+                let mut path = base_path.clone();
+                path.push_str("level ");
+                path.push_str(&level.to_string());
+                path.push('/');
+                path.push_str(file_name);
+                paths.push(path);
+            }
+        }
+
+        paths
+    }
+}
+
+pub struct TowerRegistry {
+    definitions: Vec<Rc<TowerDefinition>>,
+}
+
+impl TowerRegistry {
+    pub fn builtin() -> Result<TowerRegistry, TowerDefinitionError> {
+        TowerRegistry::from_sources(
+            BUILTIN_TOWERS
+                .iter()
+                .map(|(id, source)| (id.to_string(), source.to_string()))
+                .collect(),
+        )
+    }
+
+    /// Reads `<path>/<id>/tower.ron` for every folder in `path`. Folders
+    /// without a definition are skipped.
+    pub fn from_directory(path: &str) -> Result<TowerRegistry, TowerDefinitionError> {
+        let sources = read_definition_sources(path, TOWER_DEFINITION_FILE_NAME).map_err(
+            |(path, error)| TowerDefinitionError::Io {
+                path: path.display().to_string(),
+                message: error.to_string(),
+            },
+        )?;
+
+        TowerRegistry::from_sources(sources)
+    }
+
+    fn from_sources(sources: Vec<(String, String)>) -> Result<TowerRegistry, TowerDefinitionError> {
+        let mut definitions = vec![];
+
+        for (id, source) in sources {
+            let mut definition: TowerDefinition =
+                ron::from_str(&source).map_err(|error| TowerDefinitionError::Syntax {
+                    tower: id.clone(),
+                    message: error.to_string(),
+                })?;
+
+            if definition.levels.is_empty() {
+                return Err(TowerDefinitionError::NoLevels(id));
+            }
+
+            definition.id = id;
+            definitions.push(Rc::new(definition));
+        }
+
+        if definitions.is_empty() {
+            return Err(TowerDefinitionError::Empty);
+        }
+
+        Ok(TowerRegistry { definitions })
+    }
+
+    /// Tower types ids are only handed out by the registry, so they always
+    /// point at a definition.
+    pub fn get(&self, tower_type: TowerType) -> &Rc<TowerDefinition> {
+        &self.definitions[tower_type.0]
+    }
+
+    pub fn find(&self, id: &str) -> Option<TowerType> {
+        self.definitions
+            .iter()
+            .position(|definition| definition.id == id)
+            .map(TowerType)
+    }
+
+    pub fn get_tower_types(&self) -> Vec<TowerType> {
+        (0..self.definitions.len()).map(TowerType).collect()
+    }
+
+    pub fn get_texture_paths(&self) -> Vec<String> {
+        self.definitions
+            .iter()
+            .flat_map(|definition| definition.get_texture_paths())
+            .collect()
+    }
+}
@@ -1,18 +1,436 @@
 use crate::entities::MonsterType;
+use crate::map::GameMap;
+use crate::monster_registry::MonsterRegistry;
+use ron::extensions::Extensions;
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum WaveError {
+    Syntax(String),
+    NoWaves,
+    NoGroups { wave: usize },
+    UnknownMonster { wave: usize, monster: String },
+    UnknownSpawn { wave: usize, spawn: String },
+    UnknownExit { wave: usize, exit: String },
+    UnreachableExit { wave: usize, spawn: String, exit: String },
+}
+
+impl fmt::Display for WaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WaveError::Syntax(message) => write!(f, "Wave file is not valid RON: {}", message),
+            WaveError::NoWaves => write!(f, "Wave file has no waves"),
+            WaveError::NoGroups { wave } => {
+                write!(f, "Wave {} has no spawn groups, add at least one to \"groups\"", wave)
+            }
+            WaveError::UnknownMonster { wave, monster } => {
+                write!(f, "Wave {} spawns an unknown monster \"{}\"", wave, monster)
+            }
+            WaveError::UnknownSpawn { wave, spawn } => {
+                write!(f, "Wave {} uses spawn \"{}\" that is not on the map", wave, spawn)
+            }
+            WaveError::UnknownExit { wave, exit } => {
+                write!(f, "Wave {} uses exit \"{}\" that is not on the map", wave, exit)
+            }
+            WaveError::UnreachableExit { wave, spawn, exit } => write!(
+                f,
+                "Wave {} sends monsters from spawn \"{}\" to exit \"{}\" that can not be reached from it",
+                wave, spawn, exit
+            ),
+        }
+    }
+}
+
+impl Error for WaveError {}
+
+fn default_interval() -> f64 {
+    1000.0
+}
+
+fn default_multiplier() -> f64 {
+    1.0
+}
+
+/// Spawn group as written in a wave file, monsters and waypoints are
+/// referred to by name.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SpawnGroupDefinition {
+    pub monster: String,
+    pub count: u32,
+    /// First spawn of the map when not given.
+    #[serde(default)]
+    pub spawn: Option<String>,
+    #[serde(default)]
+    pub exit: Option<String>,
+    #[serde(default)]
+    pub delay: f64,
+    #[serde(default = "default_interval")]
+    pub interval: f64,
+    #[serde(default = "default_multiplier")]
+    pub health_multiplier: f64,
+    #[serde(default = "default_multiplier")]
+    pub speed_multiplier: f64,
+    #[serde(default)]
+    pub boss: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct WaveDefinition {
+    #[serde(default)]
+    pub completion_bonus: u32,
+    pub groups: Vec<SpawnGroupDefinition>,
+}
+
+#[derive(Deserialize)]
+struct WaveFile {
+    waves: Vec<WaveDefinition>,
+}
+
+/// Parses the waves of a wave file, they are checked against the map and
+/// the monsters once the game starts.
+pub fn read_wave_definitions(source: &str) -> Result<Vec<WaveDefinition>, WaveError> {
+    let file: WaveFile = ron::Options::default()
+        .with_default_extension(Extensions::IMPLICIT_SOME)
+        .from_str(source)
+        .map_err(|error| WaveError::Syntax(error.to_string()))?;
+
+    if file.waves.is_empty() {
+        return Err(WaveError::NoWaves);
+    }
+
+    Ok(file.waves)
+}
+
+/// Monsters of one type spawned one after another.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpawnGroup {
+    pub monster_type: MonsterType,
+    pub count: u32,
+    pub spawn: String,
+    pub exit: Option<String>,
+    /// Time in ms from the start of the wave to the first monster.
+    pub delay: f64,
+    /// Time in ms between two monsters.
+    pub interval: f64,
+    pub health_multiplier: f64,
+    pub speed_multiplier: f64,
+    pub boss: bool,
+}
+
+impl SpawnGroup {
+    fn get_spawn_time(&self, index: u32) -> f64 {
+        self.delay + self.interval * index as f64
+    }
+}
 
 #[derive(Clone)]
 pub struct Wave {
-    pub monsters_count: i32,
-    pub delay_between_spawning_monsters: f64,
-    pub monster_type: MonsterType,
+    pub groups: Vec<SpawnGroup>,
+    /// Gold paid once every monster of the wave is killed or has leaked.
+    pub completion_bonus: u32,
 }
 
 impl Wave {
     pub fn empty() -> Wave {
         Wave {
-            monsters_count: 0,
-            delay_between_spawning_monsters: 0.0,
-            monster_type: MonsterType::Creeper,
+            groups: vec![],
+            completion_bonus: 0,
         }
     }
+
+    pub fn get_monster_count(&self) -> u32 {
+        self.groups.iter().map(|group| group.count).sum()
+    }
+
+    /// Time in ms from the start of the wave to its last monster.
+    pub fn get_spawn_duration(&self) -> f64 {
+        self.groups
+            .iter()
+            .filter(|group| group.count > 0)
+            .map(|group| group.get_spawn_time(group.count - 1))
+            .fold(0.0, f64::max)
+    }
+
+    pub fn has_boss(&self) -> bool {
+        self.groups.iter().any(|group| group.boss)
+    }
+
+    /// Checks the names used by the definitions against the map and the
+    /// registry. Waves are numbered from 1 in errors.
+    pub fn from_definitions(
+        definitions: &[WaveDefinition],
+        map: &GameMap,
+        monster_registry: &MonsterRegistry,
+    ) -> Result<Vec<Wave>, WaveError> {
+        let mut waves = vec![];
+
+        for (index, definition) in definitions.iter().enumerate() {
+            let wave = index + 1;
+
+            if definition.groups.is_empty() {
+                return Err(WaveError::NoGroups { wave });
+            }
+
+            let mut groups = vec![];
+
+            for group in definition.groups.iter() {
+                let monster_type = monster_registry.find(&group.monster).ok_or_else(|| {
+                    WaveError::UnknownMonster {
+                        wave,
+                        monster: group.monster.clone(),
+                    }
+                })?;
+
+                let spawn = match &group.spawn {
+                    Some(spawn) if map.get_spawn(spawn).is_none() => {
+                        return Err(WaveError::UnknownSpawn {
+                            wave,
+                            spawn: spawn.clone(),
+                        })
+                    }
+                    Some(spawn) => spawn.clone(),
+                    None => map.spawns[0].name.clone(),
+                };
+
+                if let Some(exit) = &group.exit {
+                    if map.get_exit(exit).is_none() {
+                        return Err(WaveError::UnknownExit {
+                            wave,
+                            exit: exit.clone(),
+                        });
+                    }
+                }
+
+                groups.push(SpawnGroup {
+                    monster_type,
+                    count: group.count,
+                    spawn,
+                    exit: group.exit.clone(),
+                    delay: group.delay,
+                    interval: group.interval,
+                    health_multiplier: group.health_multiplier,
+                    speed_multiplier: group.speed_multiplier,
+                    boss: group.boss,
+                });
+            }
+
+            waves.push(Wave {
+                groups,
+                completion_bonus: definition.completion_bonus,
+            });
+        }
+
+        Ok(waves)
+    }
+}
+
+/// How far spawning a wave got.
+pub struct WaveProgress {
+    /// Index of the wave in the list of waves.
+    pub wave: usize,
+    time: f64,
+    spawned: Vec<u32>,
+}
+
+impl WaveProgress {
+    pub fn new(index: usize, wave: &Wave) -> WaveProgress {
+        WaveProgress {
+            wave: index,
+            time: 0.0,
+            spawned: vec![0; wave.groups.len()],
+        }
+    }
+
+    /// Time in ms until the last monster of the wave spawns.
+    pub fn get_remaining_time(&self, wave: &Wave) -> f64 {
+        (wave.get_spawn_duration() - self.time).max(0.0)
+    }
+
+    /// Advances by `elapsed_time` ms and returns the groups that spawn a
+    /// monster now, once for every monster.
+    pub fn update(&mut self, wave: &Wave, elapsed_time: f64) -> Vec<usize> {
+        self.time += elapsed_time;
+
+        let mut spawning = vec![];
+
+        for (index, group) in wave.groups.iter().enumerate() {
+            while self.spawned[index] < group.count
+                && group.get_spawn_time(self.spawned[index]) <= self.time
+            {
+                self.spawned[index] += 1;
+                spawning.push(index);
+            }
+        }
+
+        spawning
+    }
+
+    pub fn is_finished(&self, wave: &Wave) -> bool {
+        wave.groups
+            .iter()
+            .zip(self.spawned.iter())
+            .all(|(group, spawned)| *spawned >= group.count)
+    }
+}
+
+/// Waves of growing size played when a map doesn't come with a wave file,
+/// every third one is made of knights.
+pub fn default_waves(spawn: &str, monster_registry: &MonsterRegistry) -> Vec<Wave> {
+    let fallback = monster_registry.get_monster_types()[0];
+    let creeper = monster_registry.find("creeper").unwrap_or(fallback);
+    let knight = monster_registry.find("knight").unwrap_or(fallback);
+
+    (1..=10)
+        .map(|number| {
+            let (monster_type, count) = if number % 3 == 0 {
+                (knight, 2 + number / 3 * 2)
+            } else {
+                (creeper, 8 + number * 2)
+            };
+
+            Wave {
+                groups: vec![SpawnGroup {
+                    monster_type,
+                    count,
+                    spawn: spawn.to_string(),
+                    exit: None,
+                    delay: 0.0,
+                    interval: 1000.0 - number as f64 * 50.0,
+                    health_multiplier: 1.0,
+                    speed_multiplier: 1.0,
+                    boss: false,
+                }],
+                completion_bonus: 20 + number * 5,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn create_map() -> GameMap {
+        GameMap::from_str(
+            "(name: \"Test\", \
+             spawns: [(name: \"west\", position: (x: 0, y: 0))], \
+             exits: [(name: \"east\", position: (x: 2, y: 0))], \
+             tiles: [\"###\"])",
+        )
+        .unwrap()
+    }
+
+    fn create_waves(source: &str) -> Result<Vec<Wave>, WaveError> {
+        Wave::from_definitions(
+            &read_wave_definitions(source)?,
+            &create_map(),
+            &MonsterRegistry::builtin().unwrap(),
+        )
+    }
+
+    fn create_group(count: u32, delay: f64, interval: f64) -> SpawnGroup {
+        SpawnGroup {
+            monster_type: MonsterType(0),
+            count,
+            spawn: "west".to_string(),
+            exit: None,
+            delay,
+            interval,
+            health_multiplier: 1.0,
+            speed_multiplier: 1.0,
+            boss: false,
+        }
+    }
+
+    #[test]
+    fn reads_groups_with_defaults() {
+        let waves = create_waves(
+            "(waves: [(completion_bonus: 5, groups: [(monster: \"creeper\", count: 3)])])",
+        )
+        .unwrap();
+
+        assert_eq!(waves.len(), 1);
+        assert_eq!(waves[0].completion_bonus, 5);
+        assert_eq!(waves[0].groups[0].spawn, "west");
+        assert_eq!(waves[0].groups[0].exit, None);
+        assert_eq!(waves[0].groups[0].interval, 1000.0);
+        assert_eq!(waves[0].groups[0].health_multiplier, 1.0);
+    }
+
+    #[test]
+    fn reads_builtin_wave_files() {
+        for (name, source) in crate::map::BUILTIN_WAVES.iter() {
+            assert!(read_wave_definitions(source).is_ok(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_wave_files() {
+        assert!(matches!(
+            read_wave_definitions("(waves: [(groups: [(monster: \"creeper\")])])"),
+            Err(WaveError::Syntax(_))
+        ));
+        assert!(matches!(
+            read_wave_definitions("(waves: [])"),
+            Err(WaveError::NoWaves)
+        ));
+    }
+
+    #[test]
+    fn rejects_names_missing_from_the_map_or_registry() {
+        assert!(matches!(
+            create_waves("(waves: [(groups: [(monster: \"creeper\", count: 1)]), (groups: [])])"),
+            Err(WaveError::NoGroups { wave: 2 })
+        ));
+        assert!(matches!(
+            create_waves("(waves: [(groups: [(monster: \"dragon\", count: 1)])])"),
+            Err(WaveError::UnknownMonster { wave: 1, .. })
+        ));
+        assert!(matches!(
+            create_waves(
+                "(waves: [(groups: [(monster: \"creeper\", count: 1, spawn: \"north\")])])"
+            ),
+            Err(WaveError::UnknownSpawn { wave: 1, .. })
+        ));
+        assert!(matches!(
+            create_waves(
+                "(waves: [(groups: [(monster: \"creeper\", count: 1, exit: \"south\")])])"
+            ),
+            Err(WaveError::UnknownExit { wave: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn spawns_overlapping_groups_in_order() {
+        let wave = Wave {
+            groups: vec![create_group(3, 0.0, 1000.0), create_group(2, 1500.0, 500.0)],
+            completion_bonus: 0,
+        };
+        let mut progress = WaveProgress::new(0, &wave);
+
+        assert_eq!(progress.update(&wave, 0.0), vec![0]);
+        assert_eq!(progress.update(&wave, 1000.0), vec![0]);
+        assert_eq!(progress.update(&wave, 600.0), vec![1]);
+        assert!(!progress.is_finished(&wave));
+
+        assert_eq!(progress.update(&wave, 400.0), vec![0, 1]);
+        assert!(progress.is_finished(&wave));
+        assert_eq!(progress.update(&wave, 1000.0), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn spawns_every_monster_due_in_one_update() {
+        let wave = Wave {
+            groups: vec![create_group(3, 0.0, 1000.0), create_group(2, 1500.0, 500.0)],
+            completion_bonus: 0,
+        };
+        let mut progress = WaveProgress::new(0, &wave);
+
+        assert_eq!(progress.update(&wave, 5000.0), vec![0, 0, 0, 1, 1]);
+        assert!(progress.is_finished(&wave));
+        assert_eq!(wave.get_spawn_duration(), 2000.0);
+    }
 }
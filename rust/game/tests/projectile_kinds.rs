@@ -0,0 +1,228 @@
+use game::core::Point;
+use game::damage::{DamageType, Resistances};
+use game::entities::Monster;
+use game::monster_registry::{Animation, MonsterDefinition};
+use game::projectile::{Projectile, ProjectileDefinition, ProjectileKind, TargetLost};
+use game::tower::{TowerBehaviour, TowerLevel};
+use game::tower_registry::TowerDefinition;
+use std::cell::RefCell;
+
+const HEALTH: i32 = 100;
+const DAMAGE: i32 = 40;
+
+fn monster_definition() -> MonsterDefinition {
+    MonsterDefinition {
+        id: "dummy".to_string(),
+        name: "Dummy".to_string(),
+        health: HEALTH,
+        speed: 0.0,
+        armor: 0,
+        resistances: Resistances::default(),
+        bounty: 0,
+        lives_cost: 1,
+        animation: Animation {
+            frames: 1,
+            frame_duration: 100.0,
+        },
+    }
+}
+
+fn tower_definition(kind: ProjectileKind) -> TowerDefinition {
+    TowerDefinition {
+        id: "dummy".to_string(),
+        name: "Dummy".to_string(),
+        behaviour: TowerBehaviour::Attack,
+        effects: vec![],
+        projectile: ProjectileDefinition {
+            speed: 12.0,
+            damage_type: DamageType::Physical,
+            on_target_lost: TargetLost::Fizzle,
+            kind,
+        },
+        levels: vec![TowerLevel {
+            cost: 0,
+            damage: DAMAGE,
+            range: 10.0,
+            attack_cooldown: 1000.0,
+        }],
+    }
+}
+
+/// Monsters standing still on the given tiles.
+fn spawn(positions: &[(i32, i32)]) -> RefCell<Vec<Monster>> {
+    let definition = monster_definition();
+
+    RefCell::new(
+        positions
+            .iter()
+            .map(|&(x, y)| {
+                Monster::new(
+                    &definition,
+                    Point::new(x, y),
+                    String::new(),
+                    Point::new(0, 0),
+                )
+            })
+            .collect(),
+    )
+}
+
+/// Shoots from `tower` at the monster at `target` and lets the projectile
+/// fly until it is done.
+fn shoot(kind: ProjectileKind, tower: (i32, i32), monsters: &RefCell<Vec<Monster>>, target: usize) {
+    let definition = tower_definition(kind);
+    let mut projectile = Projectile::new(
+        Point::new(tower.0, tower.1),
+        &monsters.borrow()[target],
+        DAMAGE,
+        &definition,
+    );
+
+    for _ in 0..1000 {
+        if !projectile.active {
+            return;
+        }
+
+        projectile.update(30.0, &mut monsters.borrow_mut());
+    }
+
+    panic!("Projectile never finished");
+}
+
+fn damage_taken(monsters: &RefCell<Vec<Monster>>) -> Vec<i32> {
+    monsters
+        .borrow()
+        .iter()
+        .map(|monster| HEALTH - monster.health)
+        .collect()
+}
+
+#[test]
+fn single_hits_only_its_target() {
+    let monsters = spawn(&[(5, 5), (6, 5)]);
+
+    shoot(ProjectileKind::Single, (1, 5), &monsters, 0);
+
+    assert_eq!(damage_taken(&monsters), vec![DAMAGE, 0]);
+}
+
+#[test]
+fn splash_damage_falls_off_with_distance() {
+    let monsters = spawn(&[(5, 5), (6, 5), (5, 6), (8, 5)]);
+    let kind = ProjectileKind::Splash {
+        radius: 64,
+        edge_damage: 0.5,
+    };
+
+    shoot(kind, (5, 1), &monsters, 0);
+
+    // 32 pixels away is halfway to the edge, where damage is down to 75%.
+    // The last monster is 96 pixels away, outside of the radius.
+    assert_eq!(damage_taken(&monsters), vec![DAMAGE, 30, 30, 0]);
+}
+
+#[test]
+fn splash_damage_at_the_edge() {
+    let monsters = spawn(&[(5, 5), (7, 5)]);
+    let kind = ProjectileKind::Splash {
+        radius: 64,
+        edge_damage: 0.25,
+    };
+
+    shoot(kind, (5, 1), &monsters, 0);
+
+    assert_eq!(damage_taken(&monsters), vec![DAMAGE, 10]);
+}
+
+#[test]
+fn piercing_hits_everything_on_its_line() {
+    let monsters = spawn(&[(4, 5), (6, 5), (8, 5), (6, 7), (0, 5)]);
+
+    shoot(
+        ProjectileKind::Piercing { length: 320 },
+        (1, 5),
+        &monsters,
+        0,
+    );
+
+    // Monsters off the line or behind the tower are left alone.
+    assert_eq!(damage_taken(&monsters), vec![DAMAGE, DAMAGE, DAMAGE, 0, 0]);
+}
+
+#[test]
+fn piercing_stops_after_its_length() {
+    let monsters = spawn(&[(4, 5), (6, 5), (12, 5)]);
+
+    shoot(
+        ProjectileKind::Piercing { length: 160 },
+        (1, 5),
+        &monsters,
+        0,
+    );
+
+    assert_eq!(damage_taken(&monsters), vec![DAMAGE, DAMAGE, 0]);
+}
+
+#[test]
+fn piercing_hits_every_monster_once() {
+    let monsters = spawn(&[(3, 5)]);
+
+    // Slow enough to take several steps over the monster.
+    let mut definition = tower_definition(ProjectileKind::Piercing { length: 128 });
+    definition.projectile.speed = 4.0;
+
+    let mut projectile =
+        Projectile::new(Point::new(1, 5), &monsters.borrow()[0], DAMAGE, &definition);
+
+    while projectile.active {
+        projectile.update(30.0, &mut monsters.borrow_mut());
+    }
+
+    assert_eq!(damage_taken(&monsters), vec![DAMAGE]);
+}
+
+#[test]
+fn chain_jumps_to_closest_monsters_with_decaying_damage() {
+    let monsters = spawn(&[(5, 5), (7, 5), (6, 5), (9, 5), (14, 5)]);
+    let kind = ProjectileKind::Chain {
+        jumps: 3,
+        jump_range: 80,
+        decay: 0.5,
+    };
+
+    shoot(kind, (5, 1), &monsters, 0);
+
+    // Jumps to (6, 5) first as it is the closest, then (7, 5) and (9, 5).
+    // (14, 5) is too far from the last one.
+    assert_eq!(damage_taken(&monsters), vec![DAMAGE, 10, 20, 5, 0]);
+}
+
+#[test]
+fn chain_doesnt_hit_a_monster_twice() {
+    let monsters = spawn(&[(5, 5), (6, 5)]);
+    let kind = ProjectileKind::Chain {
+        jumps: 5,
+        jump_range: 80,
+        decay: 0.5,
+    };
+
+    shoot(kind, (5, 1), &monsters, 0);
+
+    assert_eq!(damage_taken(&monsters), vec![DAMAGE, 20]);
+}
+
+#[test]
+fn projectile_fizzles_when_its_target_dies() {
+    let monsters = spawn(&[(5, 5), (6, 5)]);
+    let definition = tower_definition(ProjectileKind::Single);
+    let mut projectile =
+        Projectile::new(Point::new(1, 5), &monsters.borrow()[0], DAMAGE, &definition);
+
+    monsters.borrow_mut()[0].health = 0;
+
+    while projectile.active {
+        projectile.update(30.0, &mut monsters.borrow_mut());
+    }
+
+    assert_eq!(damage_taken(&monsters), vec![HEALTH, 0]);
+}
@@ -1,9 +1,12 @@
 mod renderer;
-use game::core::config::TILE_PIXEL_SIZE;
+use game::core::config::DEFAULT_MAP_NAME;
 use game::core::Sprite;
 use game::core::{GameRenderer, Point};
-use game::game::Game;
-use game::{core::config::MAP_TILES_AMOUNT_Y, game::MouseState};
+use game::game::{Game, GameMode, KeyboardShortcut};
+use game::map::GameMap;
+use game::monster_registry::MonsterRegistry;
+use game::tower_registry::TowerRegistry;
+use game::game::MouseState;
 use renderer::BrowserRenderer;
 use std::cell::{Cell, RefCell};
 use std::rc::Rc;
@@ -47,12 +50,37 @@ pub async fn start() -> Result<(), JsValue> {
 
     let context = Rc::new(context);
 
-    let mut game = Game::new();
-    game.start_round();
+    let map_name = get_url_parameter(&window, "map").unwrap_or_else(|| String::from(DEFAULT_MAP_NAME));
+    let mode = match get_url_parameter(&window, "mode").as_deref() {
+        Some("maze") => GameMode::Maze,
+        _ => GameMode::Classic,
+    };
+    let map = GameMap::from_name(&map_name).map_err(|error| JsValue::from_str(&error.to_string()))?;
+
+    let towers = TowerRegistry::builtin().map_err(|error| JsValue::from_str(&error.to_string()))?;
+    let monsters = MonsterRegistry::builtin().map_err(|error| JsValue::from_str(&error.to_string()))?;
+
+    let mut texture_paths = towers.get_texture_paths();
+    texture_paths.append(&mut monsters.get_texture_paths());
+
+    let mut game = Game::new(map, towers, monsters, mode).map_err(|error| JsValue::from_str(&error.to_string()))?;
+    // "?endless=1" keeps generating waves, "&seed=<number>" replays a run.
+    if get_url_parameter(&window, "endless").is_some() {
+        let seed = match get_url_parameter(&window, "seed") {
+            Some(seed) => seed.parse::<u64>().map_err(|error| JsValue::from_str(&error.to_string()))?,
+            None => window.performance().map_or(0, |perf| perf.timing().navigation_start() as u64),
+        };
+
+        game.enable_endless_mode(seed);
+    }
+
+    let (screen_width, screen_height) = game.get_screen_size();
+    canvas.set_width(screen_width);
+    canvas.set_height(screen_height);
 
     let mut renderer = BrowserRenderer::new(context);
 
-    renderer.load_assets().await.unwrap();
+    renderer.load_assets(&texture_paths).await.unwrap();
 
     let MOUSE_X: Rc<Cell<i32>> = Rc::new(Cell::new(0));
     let MOUSE_Y: Rc<Cell<i32>> = Rc::new(Cell::new(0));
@@ -60,6 +88,10 @@ pub async fn start() -> Result<(), JsValue> {
 
     setup_mouse_events_listeners(&MOUSE_X, &MOUSE_Y, &MOUSE_BUTTONS_PRESSED);
 
+    let pressed_shortcuts: Rc<RefCell<Vec<KeyboardShortcut>>> = Rc::new(RefCell::new(vec![]));
+
+    setup_keyboard_events_listeners(&pressed_shortcuts)?;
+
     let window = web_sys::window().expect("no global `window` exists");
     if let Some(perf) = window.performance() {
         let start_time = perf.now();
@@ -77,6 +109,10 @@ pub async fn start() -> Result<(), JsValue> {
             // let mut coords = MOUSE_X.get().to_string();
             // coords.push_str(&MOUSE_Y.get().to_string());
 
+            for shortcut in pressed_shortcuts.borrow_mut().drain(..) {
+                game.handle_shortcut(shortcut);
+            }
+
             game.update(
                 elapsed_time,
                 MouseState::new(
@@ -97,7 +133,7 @@ pub async fn start() -> Result<(), JsValue> {
 
             sprites.push(Sprite::create_text(
                 &fps_msg,
-                Point::new(10, TILE_PIXEL_SIZE * MAP_TILES_AMOUNT_Y as i32),
+                Point::new(10, game.get_map().pixel_height()),
                 16,
             ));
 
@@ -125,6 +161,19 @@ pub async fn start() -> Result<(), JsValue> {
     Ok(())
 }
 
+// Reads a "?<name>=<value>" query parameter, e.g. "?map=default&mode=maze".
+fn get_url_parameter(window: &web_sys::Window, name: &str) -> Option<String> {
+    let search = window.location().search().ok()?;
+
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .filter_map(|parameter| parameter.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_string())
+        .filter(|value| !value.is_empty())
+}
+
 fn setup_mouse_events_listeners(
     mouse_x: &Rc<Cell<i32>>,
     mouse_y: &Rc<Cell<i32>>,
@@ -163,6 +212,37 @@ fn setup_mouse_events_listeners(
     }
 }
 
+// Shortcuts are queued here and handed to the game on the next frame.
+fn setup_keyboard_events_listeners(
+    pressed_shortcuts: &Rc<RefCell<Vec<KeyboardShortcut>>>,
+) -> Result<(), JsValue> {
+    let window = web_sys::window().expect("no global `window` exists");
+    let document = window.document().expect("no document");
+
+    let pressed_shortcuts_ref = pressed_shortcuts.clone();
+
+    let closure = Closure::wrap(Box::new(move |event: web_sys::KeyboardEvent| {
+        let shortcut = match event.key().as_ref() {
+            "u" | "U" => KeyboardShortcut::UpgradeTower,
+            "s" | "S" => KeyboardShortcut::SellTower,
+            "t" | "T" => KeyboardShortcut::CycleTargetingMode,
+            "n" | "N" => KeyboardShortcut::CallNextWave,
+            "Enter" => KeyboardShortcut::StartGame,
+            "p" | "P" | "Escape" => KeyboardShortcut::TogglePause,
+            "r" | "R" => KeyboardShortcut::Restart,
+            // A page can't close itself, quitting goes back to the menu.
+            "q" | "Q" => KeyboardShortcut::Quit,
+            _ => return,
+        };
+
+        pressed_shortcuts_ref.borrow_mut().push(shortcut);
+    }) as Box<dyn FnMut(_)>);
+    document.add_event_listener_with_callback("keydown", closure.as_ref().unchecked_ref())?;
+    closure.forget();
+
+    Ok(())
+}
+
 // run_draw_loop();
 // renderer.draw(&sprites);
 // let monster = Rc::new(RefCell::new(Monster::new(
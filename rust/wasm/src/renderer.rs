@@ -216,29 +216,17 @@ impl BrowserRenderer {
 }
 
 impl BrowserRenderer {
-    pub async fn load_assets(&mut self) -> Result<String, String> {
+    /// Tower and monster textures depend on the loaded definitions, so they
+    /// are passed in along with the fixed list below.
+    pub async fn load_assets(&mut self, texture_paths: &[String]) -> Result<String, String> {
         let assets: Vec<&str> = vec![
             "/assets/tiles/ground_1.png",
             "/assets/tiles/ice_1.png",
-            "/assets/creatures/creeper/right_0.png",
-            "/assets/creatures/creeper/right_1.png",
-            "/assets/creatures/creeper/right_2.png",
-            "/assets/creatures/creeper/bottom_0.png",
-            "/assets/creatures/creeper/bottom_1.png",
-            "/assets/creatures/creeper/bottom_2.png",
-            "/assets/creatures/creeper/top_0.png",
-            "/assets/creatures/creeper/top_1.png",
-            "/assets/creatures/creeper/top_2.png",
-            "/assets/creatures/creeper/left_0.png",
-            "/assets/creatures/creeper/left_1.png",
-            "/assets/creatures/creeper/left_2.png",
             "/assets/interface/background.png",
             "/assets/interface/icon_lifes.png",
-            "/assets/towers/orc/level 1/full.png",
-            "/assets/towers/orc/level 1/icon.png",
-            "/assets/towers/orc/shoot.png",
             "/assets/tiles/map.png",
             "/assets/interface/icon_score.png",
+            "/assets/interface/icon_gold.png",
             "/assets/interface/slot.png",
         ];
 
@@ -246,6 +234,10 @@ impl BrowserRenderer {
             self.texture_cache.load_texture(asset).await;
         }
 
+        for asset in texture_paths.iter() {
+            self.texture_cache.load_texture(asset).await;
+        }
+
         unsafe {
             log("All visual assets loaded.");
         };
@@ -255,8 +247,13 @@ impl BrowserRenderer {
 
 impl GameRenderer for BrowserRenderer {
     fn draw(&mut self, sprites: &Vec<Sprite>) -> Result<(), String> {
+        let (width, height) = match self.canvas.canvas() {
+            Some(canvas) => (canvas.width() as f64, canvas.height() as f64),
+            None => (900.0, 900.0),
+        };
+
         self.canvas.set_fill_style(&"rgb(5,5,5)".into());
-        self.canvas.fill_rect(0.0, 0.0, 900.0, 900.0);
+        self.canvas.fill_rect(0.0, 0.0, width, height);
 
         for sprite in sprites {
             match sprite.sprite_type {
@@ -315,6 +312,30 @@ impl GameRenderer for BrowserRenderer {
                     );
                     // self.canvas
                 }
+                SpriteType::Circle => {
+                    let mut fill_style = "rgba(".to_owned();
+                    fill_style.push_str(&sprite.color.r.to_string());
+                    fill_style.push_str(",");
+                    fill_style.push_str(&sprite.color.g.to_string());
+                    fill_style.push_str(",");
+                    fill_style.push_str(&sprite.color.b.to_string());
+                    fill_style.push_str(",");
+                    fill_style.push_str(&((sprite.color.a as f64) / 255.0).to_string());
+                    fill_style.push_str(")");
+                    self.canvas.set_fill_style(&fill_style.into());
+
+                    self.canvas.begin_path();
+                    self.canvas
+                        .arc(
+                            sprite.position.x as f64,
+                            sprite.position.y as f64,
+                            sprite.width as f64,
+                            0.0,
+                            std::f64::consts::PI * 2.0,
+                        )
+                        .unwrap();
+                    self.canvas.fill();
+                }
                 SpriteType::Text => {
                     let r = "255";
                     let g = "255";